@@ -0,0 +1,11 @@
+pub mod lyapunov;
+pub use lyapunov::{lyapunov_spectrum, largest_lyapunov};
+
+pub mod dimension;
+pub use dimension::{correlation_dimension, box_counting_dimension, dimension_scalings, Scaling};
+
+pub mod search;
+pub use search::{find_chaotic, SearchOptions};
+
+pub mod parameter_plane;
+pub use parameter_plane::{gen_parameter_plane, PlaneOptions, PlaneMetric};
@@ -0,0 +1,206 @@
+use std::sync::atomic::Ordering;
+use crate::attractors::Attractor;
+use crate::render::Progress;
+
+// number of radii of the correlation sum, spaced geometrically over three decades
+const RADII: usize = 24;
+// iterations between checks for cancellation
+const CHECK_ITERS: usize = 1 << 12;
+
+// iterations between samples, flows are sampled every 0.1 time units so the points decorrelate
+pub fn default_stride<A: Attractor + ?Sized>(at: &A) -> usize {
+    match at.state().get_dt() {
+        Some(dt) if dt > 0.0 => ((0.1 / dt).round() as usize).max(1),
+        _ => 1,
+    }
+}
+
+// `n` states of the trajectory after `skip` iterations, sampled every `stride` iterations.
+// stops early if the orbit diverges.
+pub fn sample_states<A: Attractor + ?Sized>(at: &mut A, n: usize, skip: usize, stride: usize) -> Vec<Vec<f64>> {
+    sample_states_cancellable(at, n, skip, stride, &Progress::default()).unwrap_or_default()
+}
+
+// sample_states reporting the iterations done, None when cancelled
+fn sample_states_cancellable<A: Attractor + ?Sized>(
+    at: &mut A, n: usize, skip: usize, stride: usize, progress: &Progress
+) -> Option<Vec<Vec<f64>>> {
+    if !at.prepare() {return Some(vec![]);}
+    let stride = stride.max(1);
+    progress.total.store(skip + n * stride, Ordering::Relaxed);
+    at.state_mut().set_init();
+    for i in 0..skip {
+        if i % CHECK_ITERS == 0 {
+            if progress.is_cancelled() {return None;}
+            progress.done.store(i, Ordering::Relaxed);
+        }
+        at.apply_map_func();
+    }
+    let mut points = Vec::with_capacity(n);
+    for i in 0..n {
+        if (i * stride) % CHECK_ITERS < stride {
+            if progress.is_cancelled() {return None;}
+            progress.done.store(skip + i * stride, Ordering::Relaxed);
+        }
+        for _ in 0..stride {
+            at.apply_map_func();
+        }
+        let x = at.state().get_xs();
+        if x.iter().any(|v| !v.is_finite()) {break;}
+        points.push(x.to_vec());
+    }
+    at.state_mut().set_init();
+    Some(points)
+}
+
+// radii from 1/1000 of the extent of the points up to the extent
+pub fn default_radii(points: &[Vec<f64>]) -> Vec<f64> {
+    let Some(first) = points.first() else {return vec![];};
+    let extent = (0..first.len())
+        .map(|i| {
+            let (lo, hi) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| (lo.min(p[i]), hi.max(p[i])));
+            (hi - lo) * (hi - lo)
+        })
+        .sum::<f64>()
+        .sqrt();
+    (0..RADII)
+        .map(|i| extent * 1e-3f64.powf(1.0 - i as f64 / (RADII - 1) as f64))
+        .collect()
+}
+
+// Grassberger-Procaccia correlation sum C(r), the fraction of pairs closer than r, for ascending radii
+pub fn correlation_sums(points: &[Vec<f64>], radii: &[f64]) -> Vec<f64> {
+    let mut counts = vec![0usize; radii.len()];
+    for (i, p) in points.iter().enumerate() {
+        for q in &points[i + 1..] {
+            let d = p.iter().zip(q.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f64>().sqrt();
+            if let Some(k) = radii.iter().position(|&r| d < r) {
+                counts[k] += 1;
+            }
+        }
+    }
+    let pairs = (points.len() * points.len().saturating_sub(1) / 2).max(1) as f64;
+    let mut total = 0;
+    counts.into_iter()
+        .map(|c| {
+            total += c;
+            total as f64 / pairs
+        })
+        .collect()
+}
+
+// least squares slope of ln y over ln x
+pub fn log_log_slope(xs: &[f64], ys: &[f64]) -> f64 {
+    let pts = xs.iter().zip(ys.iter())
+        .filter(|(x, y)| **x > 0.0 && **y > 0.0)
+        .map(|(x, y)| (x.ln(), y.ln()))
+        .collect::<Vec<_>>();
+    slope(&pts)
+}
+
+// least squares slope of y over x
+fn slope(pts: &[(f64, f64)]) -> f64 {
+    if pts.len() < 2 {return f64::NAN;}
+    let n = pts.len() as f64;
+    let (mx, my) = pts.iter().fold((0.0, 0.0), |(a, b), (x, y)| (a + x / n, b + y / n));
+    let sxy = pts.iter().map(|(x, y)| (x - mx) * (y - my)).sum::<f64>();
+    let sxx = pts.iter().map(|(x, _)| (x - mx) * (x - mx)).sum::<f64>();
+    if sxx == 0.0 {f64::NAN} else {sxy / sxx}
+}
+
+// a log-log curve of a measure over the scale, the dimension is the slope over the scaling region
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scaling {
+    pub log_scales: Vec<f64>,
+    pub log_measures: Vec<f64>,
+    // indices of the points fitted
+    pub fit: std::ops::Range<usize>,
+    pub dimension: f64,
+}
+
+impl Scaling {
+    // fits the longest run of points accepted by `keep`
+    fn new(xs: Vec<f64>, ys: Vec<f64>, keep: impl Fn(usize) -> bool) -> Self {
+        let (mut fit, mut start) = (0..0, None);
+        for i in 0..=xs.len() {
+            match (i < xs.len() && keep(i) && xs[i].is_finite() && ys[i].is_finite(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    if i - s > fit.len() {fit = s..i;}
+                    start = None;
+                },
+                _ => {},
+            }
+        }
+        let dimension = slope(&fit.clone().map(|i| (xs[i], ys[i])).collect::<Vec<_>>());
+        Self { log_scales: xs, log_measures: ys, fit, dimension }
+    }
+}
+
+// ln C(r) over ln r, fitted where at least 20 pairs are counted and C(r) is not yet saturated
+pub fn correlation_scaling(points: &[Vec<f64>]) -> Scaling {
+    let radii = default_radii(points);
+    let sums = correlation_sums(points, &radii);
+    let pairs = (points.len() * points.len().saturating_sub(1) / 2).max(1) as f64;
+    let keep = |i: usize| sums[i] >= 20.0 / pairs && sums[i] <= 0.2;
+    Scaling::new(radii.iter().map(|r| r.ln()).collect(), sums.iter().map(|c| c.ln()).collect(), keep)
+}
+
+// correlation dimension, NaN for degenerate point sets
+pub fn correlation_dimension(points: &[Vec<f64>]) -> f64 {
+    correlation_scaling(points).dimension
+}
+
+// number of boxes of side `size` holding at least one point, for each size
+pub fn box_counts(points: &[Vec<f64>], sizes: &[f64]) -> Vec<usize> {
+    let Some(first) = points.first() else {return vec![0; sizes.len()];};
+    let lo = (0..first.len())
+        .map(|i| points.iter().fold(f64::MAX, |lo, p| lo.min(p[i])))
+        .collect::<Vec<_>>();
+    sizes.iter()
+        .map(|&size| {
+            points.iter()
+                .map(|p| p.iter().zip(lo.iter()).map(|(v, l)| ((v - l) / size).floor() as i64).collect::<Vec<_>>())
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        })
+        .collect()
+}
+
+// ln N(s) over ln 1/s for box sides from the extent of the points down to 1/1000 of it, fitted from
+// 8 boxes up to where the boxes hold 50 points on average and the attractor is no longer covered
+pub fn box_counting_scaling(points: &[Vec<f64>]) -> Scaling {
+    let sizes = default_radii(points).into_iter().rev().collect::<Vec<_>>();
+    let counts = box_counts(points, &sizes);
+    let keep = |i: usize| counts[i] >= 8 && counts[i] * 50 <= points.len();
+    Scaling::new(sizes.iter().map(|s| -s.ln()).collect(), counts.iter().map(|&n| (n as f64).ln()).collect(), keep)
+}
+
+// box-counting dimension, NaN for degenerate point sets
+pub fn box_counting_dimension(points: &[Vec<f64>]) -> f64 {
+    box_counting_scaling(points).dimension
+}
+
+// correlation and box-counting curves of the trajectory of `at`. the correlation sum is quadratic in
+// the points so it gets fewer of them. None when cancelled
+pub fn dimension_scalings<A: Attractor + ?Sized>(at: &mut A, progress: &Progress) -> Option<(Scaling, Scaling)> {
+    let stride = default_stride(at);
+    let points = sample_states_cancellable(at, 50000, 1000 * stride, stride, progress)?;
+    let sub = points.len().div_ceil(2000).max(1);
+    let sparse = points.iter().step_by(sub).cloned().collect::<Vec<_>>();
+    Some((correlation_scaling(&sparse), box_counting_scaling(&points)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractors::Henon;
+
+    // Grassberger and Procaccia measured 1.21 for the Henon map
+    #[test]
+    fn henon_correlation_dimension() {
+        let points = sample_states(&mut Henon::default(), 4000, 1000, 1);
+        let dimension = correlation_dimension(&points);
+        assert!((dimension - 1.21).abs() < 0.1, "{}", dimension);
+    }
+}
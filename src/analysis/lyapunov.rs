@@ -0,0 +1,142 @@
+use std::f64::consts::{PI, TAU};
+use std::sync::atomic::Ordering;
+use crate::attractors::Attractor;
+use crate::render::Progress;
+
+// size of the finite difference used to push tangent vectors through apply_map_func
+const EPS: f64 = 1e-7;
+// iterations between checks for cancellation
+const CHECK_ITERS: usize = 1 << 12;
+
+// iterations and transient giving stable estimates, flows are integrated for a fixed time span
+pub fn default_iters<A: Attractor + ?Sized>(at: &A) -> (usize, usize) {
+    match at.state().get_dt() {
+        Some(dt) if dt > 0.0 => {
+            let n = ((200.0 / dt) as usize).clamp(10000, 2000000);
+            (n, n / 10)
+        },
+        _ => (20000, 1000),
+    }
+}
+
+// largest `k` Lyapunov exponents, per iteration for maps and per unit time for flows.
+// tangent vectors are evolved with finite differences of apply_map_func and renormalised with
+// Gram-Schmidt every step. NaN if the system cannot be iterated or the orbit diverges, None when
+// cancelled.
+pub fn lyapunov_spectrum<A: Attractor + ?Sized>(
+    at: &mut A, n: usize, skip: usize, k: usize, progress: &Progress
+) -> Option<Vec<f64>> {
+    let dim = at.state().get_xs().len();
+    let k = k.min(dim);
+    if !at.prepare() {return Some(vec![f64::NAN; k]);}
+    progress.total.store(skip + n, Ordering::Relaxed);
+
+    at.state_mut().set_init();
+    for i in 0..skip {
+        if i % CHECK_ITERS == 0 {
+            if progress.is_cancelled() {return None;}
+            progress.done.store(i, Ordering::Relaxed);
+        }
+        at.apply_map_func();
+    }
+
+    let mut vs = (0..k)
+        .map(|i| (0..dim).map(|j| if i == j {1.0} else {0.0}).collect::<Vec<f64>>())
+        .collect::<Vec<_>>();
+    let mut sums = vec![0.0; k];
+    let angles = at.angles();
+    let (mut x, mut fx) = (vec![0.0; dim], vec![0.0; dim]);
+    let start = at.state().time;
+    for i in 0..n {
+        if i % CHECK_ITERS == 0 {
+            if progress.is_cancelled() {
+                at.state_mut().set_init();
+                return None;
+            }
+            progress.done.store(skip + i, Ordering::Relaxed);
+        }
+        x.copy_from_slice(at.state().get_xs());
+        let t = at.state().time;
+        at.apply_map_func();
+        fx.copy_from_slice(at.state().get_xs());
+        let ft = at.state().time;
+        if fx.iter().any(|v| !v.is_finite()) {
+            at.state_mut().set_init();
+            return Some(vec![f64::NAN; k]);
+        }
+
+        for v in vs.iter_mut() {
+            at.state_mut().get_xs_mut().iter_mut().zip(x.iter().zip(v.iter()))
+                .for_each(|(xs, (x, v))| *xs = x + EPS * v);
+            at.state_mut().time = t;
+            at.apply_map_func();
+            for (j, (v, (y, fx))) in v.iter_mut().zip(at.state().get_xs().iter().zip(fx.iter())).enumerate() {
+                let mut d = y - fx;
+                // angles wrapped into a period (DoublePendulum) jump by TAU
+                if d.abs() > PI && angles.contains(&j) {
+                    d -= TAU * (d / TAU).round();
+                }
+                *v = d / EPS;
+            }
+        }
+        at.state_mut().get_xs_mut().copy_from_slice(&fx);
+        at.state_mut().time = ft;
+
+        for (i, sum) in sums.iter_mut().enumerate() {
+            let (done, rest) = vs.split_at_mut(i);
+            let v = &mut rest[0];
+            for u in done.iter() {
+                let dot = v.iter().zip(u.iter()).map(|(a, b)| a * b).sum::<f64>();
+                v.iter_mut().zip(u.iter()).for_each(|(a, b)| *a -= dot * b);
+            }
+            let norm = v.iter().map(|a| a * a).sum::<f64>().sqrt();
+            if !norm.is_finite() {
+                at.state_mut().set_init();
+                return Some(vec![f64::NAN; k]);
+            }
+            // the map collapses this direction, restart it from a basis vector
+            if norm == 0.0 {
+                *sum = f64::NEG_INFINITY;
+                v.iter_mut().enumerate().for_each(|(j, a)| *a = if i == j {1.0} else {0.0});
+                continue;
+            }
+            *sum += norm.ln();
+            v.iter_mut().for_each(|a| *a /= norm);
+        }
+    }
+
+    let span = if at.state().get_dt().is_some() {at.state().time - start} else {n as f64};
+    at.state_mut().set_init();
+    Some(sums.into_iter().map(|s| s / span).collect())
+}
+
+pub fn largest_lyapunov<A: Attractor + ?Sized>(at: &mut A, n: usize, skip: usize) -> f64 {
+    lyapunov_spectrum(at, n, skip, 1, &Progress::default()).map_or(f64::NAN, |s| s[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractors::{Henon, Lorenz};
+    use crate::state::State;
+
+    // published values: Henon (a = 1.4, b = 0.3) 0.419 and ln(b) - 0.419, Lorenz (10, 28, 8/3) 0.906
+    #[test]
+    fn henon_spectrum() {
+        let mut henon = Henon::default();
+        let spectrum = lyapunov_spectrum(&mut henon, 200000, 1000, 2, &Progress::default()).unwrap();
+        assert!((spectrum[0] - 0.419).abs() < 0.01, "{:?}", spectrum);
+        assert!((spectrum[1] - (0.3f64.ln() - 0.419)).abs() < 0.01, "{:?}", spectrum);
+    }
+
+    #[test]
+    fn lorenz_largest() {
+        let mut lorenz = Lorenz {
+            coefs: vec![10.0, 28.0, 8.0 / 3.0],
+            state: State::new(3, 0.0..=20.0, Some(0.01)).with_init(&[1.0, 1.0, 1.0]),
+            ..Default::default()
+        };
+        let exponent = largest_lyapunov(&mut lorenz, 100000, 1000);
+        assert!((exponent - 0.906).abs() < 0.05, "{}", exponent);
+    }
+}
@@ -0,0 +1,183 @@
+use rayon::prelude::*;
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+
+use super::largest_lyapunov;
+use crate::attractors::Attractor;
+use crate::render::Progress;
+
+// orbits leaving this radius are treated as escaping to infinity
+const ESCAPE: f64 = 1e6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaneMetric {
+    // largest Lyapunov exponent, NaN where the orbit diverges
+    Lyapunov,
+    // period of the orbit, 0 when no period up to max_period is found, NaN where it diverges
+    Period,
+    // iterations until the orbit escapes, NaN where it stays bounded
+    Divergence,
+}
+
+impl PlaneMetric {
+    pub const ALL: [PlaneMetric; 3] = [PlaneMetric::Lyapunov, PlaneMetric::Period, PlaneMetric::Divergence];
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlaneMetric::Lyapunov => "Lyapunov exponent",
+            PlaneMetric::Period => "period",
+            PlaneMetric::Divergence => "divergence",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaneOptions {
+    pub metric: PlaneMetric,
+    // indices into coefs() swept along the horizontal and vertical axis
+    pub coefs: [usize; 2],
+    pub ranges: [RangeInclusive<f64>; 2],
+    pub iters: usize,
+    pub skip: usize,
+    pub max_period: usize,
+}
+
+impl PlaneOptions {
+    // the first two coefficients over their ranges, flows are run for 20 time units.
+    // None when the system has no coefficients
+    pub fn new<A: Attractor + ?Sized>(at: &A) -> Option<Self> {
+        let ranges = at.coef_ranges();
+        let second = 1.min(ranges.len().checked_sub(1)?);
+        let (iters, skip) = match at.state().get_dt() {
+            Some(dt) => {
+                let n = ((20.0 / dt) as usize).clamp(1000, 200000);
+                (n, n / 4)
+            },
+            None => (2000, 500),
+        };
+        Some(Self {
+            metric: PlaneMetric::Lyapunov,
+            coefs: [0, second],
+            ranges: [ranges[0].clone(), ranges[second].clone()],
+            iters,
+            skip,
+            max_period: 64,
+        })
+    }
+    // coefficients at the center of pixel (x, y) of a w x h image, larger values at the top
+    pub fn coefs_at(&self, x: usize, y: usize, w: usize, h: usize) -> (f64, f64) {
+        let coord = |r: &RangeInclusive<f64>, i: usize, n: usize| r.start() + (r.end() - r.start()) * (i as f64 + 0.5) / n as f64;
+        (coord(&self.ranges[0], x, w), coord(&self.ranges[1], h - 1 - y, h))
+    }
+}
+
+// smallest p with x_n = x_{n-p} at the end of the orbit
+fn period<A: Attractor + ?Sized>(at: &mut A, opts: &PlaneOptions) -> f64 {
+    at.state_mut().set_init();
+    for _ in 0..opts.skip {
+        at.apply_map_func();
+    }
+    let mut recent: Vec<Vec<f64>> = Vec::with_capacity(opts.max_period);
+    for _ in 0..opts.max_period {
+        at.apply_map_func();
+        recent.push(at.state().get_xs().to_vec());
+    }
+    let x = recent.last().cloned().unwrap_or_default();
+    if x.iter().any(|v| !v.is_finite() || v.abs() > ESCAPE) {return f64::NAN;}
+    let scale = x.iter().map(|v| v.abs()).fold(1.0, f64::max);
+    (1..recent.len())
+        .find(|p| recent[recent.len() - 1 - p].iter().zip(x.iter()).all(|(a, b)| (a - b).abs() < 1e-8 * scale))
+        .map_or(0.0, |p| p as f64)
+}
+
+fn divergence<A: Attractor + ?Sized>(at: &mut A, opts: &PlaneOptions) -> f64 {
+    at.state_mut().set_init();
+    for i in 1..=opts.iters {
+        at.apply_map_func();
+        if at.state().get_xs().iter().any(|v| !v.is_finite() || v.abs() > ESCAPE) {
+            return i as f64;
+        }
+    }
+    f64::NAN
+}
+
+// the metric of every pixel of a w x h image over the two coefficients, None when cancelled.
+// every worker iterates its own copy of the system
+pub fn gen_parameter_plane<A: Attractor + ?Sized>(
+    at: &A, opts: &PlaneOptions, w: usize, h: usize, progress: &Progress
+) -> Option<Vec<f64>> {
+    let n = at.coefs().len();
+    // nothing to sweep
+    if n == 0 {return Some(vec![f64::NAN; w * h]);}
+    let [a, b] = opts.coefs.map(|k| k.min(n - 1));
+    progress.total.store(h, Ordering::Relaxed);
+    progress.done.store(0, Ordering::Relaxed);
+
+    let template = Mutex::new(at.clone_box());
+    let rows = (0..h).into_par_iter()
+        .map_init(|| template.lock().unwrap().clone_box(), |row, y| {
+            let values = (0..w)
+                .map(|x| {
+                    if progress.is_cancelled() {return None;}
+                    let (ca, cb) = opts.coefs_at(x, y, w, h);
+                    row.coefs_mut()[a] = ca;
+                    row.coefs_mut()[b] = cb;
+                    if !row.prepare() {return Some(f64::NAN);}
+                    Some(match opts.metric {
+                        PlaneMetric::Lyapunov => largest_lyapunov(row.as_mut(), opts.iters, opts.skip),
+                        PlaneMetric::Period => period(row.as_mut(), opts),
+                        PlaneMetric::Divergence => divergence(row.as_mut(), opts),
+                    })
+                })
+                .collect::<Option<Vec<_>>>();
+            progress.done.fetch_add(1, Ordering::Relaxed);
+            values
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(rows.concat())
+}
+
+// values in 0.0..=1.0 for the palette, NaN stays NaN.
+// exponents are centered on 0.5 with chaos above, periods are scaled by max_period with aperiodic
+// orbits at 1.0 and divergence times are log scaled
+pub fn levels(values: &[f64], opts: &PlaneOptions) -> Vec<f64> {
+    match opts.metric {
+        PlaneMetric::Lyapunov => {
+            let scale = values.iter().filter(|v| v.is_finite()).fold(0.0f64, |m, v| m.max(v.abs()));
+            // superstable orbits have an exponent of -inf
+            values.iter()
+                .map(|v| if v.is_nan() || scale == 0.0 {*v} else {(0.5 + 0.5 * v / scale).clamp(0.0, 1.0)})
+                .collect()
+        },
+        PlaneMetric::Period => {
+            let max = opts.max_period as f64;
+            values.iter().map(|&p| if p == 0.0 {1.0} else {p / max}).collect()
+        },
+        PlaneMetric::Divergence => {
+            let max = (1.0 + opts.iters as f64).ln();
+            values.iter().map(|v| (1.0 + v).ln() / max).collect()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractors::Custom;
+
+    #[test]
+    fn no_coefficients() {
+        let custom = Custom::new("sin(y);cos(x)").unwrap();
+        assert!(PlaneOptions::new(&custom).is_none());
+        let opts = PlaneOptions {
+            metric: PlaneMetric::Lyapunov,
+            coefs: [0, 1],
+            ranges: [0.0..=1.0, 0.0..=1.0],
+            iters: 100,
+            skip: 10,
+            max_period: 8,
+        };
+        let values = gen_parameter_plane(&custom, &opts, 4, 3, &Progress::default()).unwrap();
+        assert_eq!(values.len(), 12);
+    }
+}
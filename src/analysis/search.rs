@@ -0,0 +1,110 @@
+use std::sync::atomic::Ordering;
+
+use super::{largest_lyapunov, dimension};
+use crate::attractors::Attractor;
+use crate::render::Progress;
+
+// orbits leaving this radius are treated as escaping to infinity
+const ESCAPE: f64 = 1e6;
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub max_tries: usize,
+    // in nats per iteration for maps and per unit time for flows
+    pub min_lyapunov: f64,
+    pub min_dimension: f64,
+    // points used for the correlation dimension
+    pub dimension_points: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            max_tries: 1000,
+            min_lyapunov: 0.005,
+            min_dimension: 1.0,
+            dimension_points: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub coefs: Vec<f64>,
+    pub lyapunov: f64,
+    pub dimension: f64,
+    pub tries: usize,
+}
+
+// short runs for screening, flows are integrated for 50 time units
+fn quick_iters<A: Attractor + ?Sized>(at: &A) -> (usize, usize) {
+    match at.state().get_dt() {
+        Some(dt) if dt > 0.0 => {
+            let n = ((50.0 / dt) as usize).clamp(1000, 500000);
+            (n, n / 5)
+        },
+        _ => (5000, 1000),
+    }
+}
+
+// reject orbits which escape, settle on a fixed point or close on a short cycle
+fn is_bounded_aperiodic<A: Attractor + ?Sized>(at: &mut A, n: usize) -> bool {
+    if !at.prepare() {return false;}
+    at.state_mut().set_init();
+    let mut recent: Vec<Vec<f64>> = vec![];
+    let mut ok = true;
+    for i in 0..n {
+        at.apply_map_func();
+        let x = at.state().get_xs();
+        if x.iter().any(|v| !v.is_finite() || v.abs() > ESCAPE) {
+            ok = false;
+            break;
+        }
+        // compare the last iterations with the previous ones to find fixed points and cycles
+        if i + 64 >= n {
+            let scale = x.iter().map(|v| v.abs()).fold(1.0, f64::max);
+            if recent.iter().any(|p| p.iter().zip(x.iter()).all(|(a, b)| (a - b).abs() < 1e-10 * scale)) {
+                ok = false;
+                break;
+            }
+            recent.push(x.to_vec());
+        }
+    }
+    at.state_mut().set_init();
+    ok
+}
+
+// Sprott-style search: sample coefficients until the orbit is bounded, aperiodic, has a positive
+// largest Lyapunov exponent and a fractal correlation dimension. The coefficients of the candidate
+// are left in `at`; if nothing is found or the search is cancelled the original coefficients are restored.
+pub fn find_chaotic<A: Attractor + ?Sized>(at: &mut A, options: &SearchOptions, progress: &Progress) -> Option<Candidate> {
+    let original = at.coefs().to_vec();
+    let (n, skip) = quick_iters(at);
+    let dim = at.state().get_xs().len() as f64;
+    let stride = dimension::default_stride(at);
+    progress.total.store(options.max_tries, Ordering::Relaxed);
+    progress.done.store(0, Ordering::Relaxed);
+    for tries in 1..=options.max_tries {
+        if progress.is_cancelled() {break;}
+        progress.done.store(tries, Ordering::Relaxed);
+        at.change_random_coefs();
+        if at.state().get_dt().is_none() && !is_bounded_aperiodic(at, n) {continue;}
+
+        let lyapunov = largest_lyapunov(at, n, skip);
+        if lyapunov.is_nan() || lyapunov <= options.min_lyapunov {continue;}
+
+        let points = dimension::sample_states(at, options.dimension_points, skip, stride);
+        if points.len() < options.dimension_points {continue;}
+        let dimension = dimension::correlation_dimension(&points);
+        if dimension.is_nan() || dimension <= options.min_dimension || dimension > dim {continue;}
+
+        return Some(Candidate {
+            coefs: at.coefs().to_vec(),
+            lyapunov,
+            dimension,
+            tries,
+        });
+    }
+    at.coefs_mut().copy_from_slice(&original);
+    None
+}
@@ -0,0 +1,242 @@
+use anyhow::{Result, anyhow};
+use image::{DynamicImage, Delay, Frame};
+use image::codecs::gif::{GifEncoder, Repeat};
+use serde::{Serialize, Deserialize};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+use crate::attractors::Attractor;
+use crate::params;
+use crate::render::{self, Progress};
+use crate::util::Palette;
+
+// the coefficients, initial values and palette at `time` in 0.0..=1.0 of the animation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64,
+    pub coefs: Vec<f64>,
+    pub init: Vec<f64>,
+    pub palette: Palette,
+}
+
+impl Keyframe {
+    pub fn new<A: Attractor + ?Sized>(time: f64, at: &A, plt: &Palette) -> Self {
+        Self {
+            time,
+            coefs: at.coefs().to_vec(),
+            init: at.state().get_init_val().to_vec(),
+            palette: plt.clone(),
+        }
+    }
+}
+
+// keyframes spread evenly over the animation, in order
+pub fn space_evenly(keys: &mut [Keyframe]) {
+    let n = keys.len();
+    for (i, key) in keys.iter_mut().enumerate() {
+        key.time = if n > 1 {i as f64 / (n - 1) as f64} else {0.0};
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    // smoothstep between two keyframes, the motion slows down at every keyframe
+    Smooth,
+}
+
+impl Easing {
+    fn apply(&self, s: f64) -> f64 {
+        match self {
+            Easing::Linear => s,
+            Easing::Smooth => s * s * (3.0 - 2.0 * s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationFormat {
+    // numbered PNGs next to the output, each with its parameters embedded
+    PngSequence,
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub const ALL: [AnimationFormat; 3] = [AnimationFormat::PngSequence, AnimationFormat::Gif, AnimationFormat::Apng];
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnimationFormat::PngSequence => "PNG sequence",
+            AnimationFormat::Gif => "GIF",
+            AnimationFormat::Apng => "APNG",
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::PngSequence => "png",
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "apng",
+        }
+    }
+    // the format of the extension of `path`, .png is a sequence
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Self::ALL.into_iter().find(|f| f.extension() == ext)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    pub format: AnimationFormat,
+    pub frames: usize,
+    pub fps: u16,
+    pub easing: Easing,
+    // iterations of every frame
+    pub num_iter: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            format: AnimationFormat::Gif,
+            frames: 60,
+            fps: 30,
+            easing: Easing::Smooth,
+            num_iter: 1000000,
+            width: 512,
+            height: 512,
+        }
+    }
+}
+
+// the parameters at `time`, interpolated between the keyframes around it. `keys` are sorted by time
+pub fn interpolate(keys: &[Keyframe], time: f64, easing: Easing) -> Option<Keyframe> {
+    let i = keys.iter().rposition(|k| k.time <= time).unwrap_or(0);
+    let a = keys.get(i)?;
+    let b = keys.get(i + 1).unwrap_or(a);
+    let span = b.time - a.time;
+    let s = easing.apply(if span > 0.0 {((time - a.time) / span).clamp(0.0, 1.0)} else {0.0});
+    let l = |x: f64, y: f64| x + (y - x) * s;
+    let lv = |x: &[f64], y: &[f64]| x.iter().zip(y.iter()).map(|(x, y)| l(*x, *y)).collect::<Vec<_>>();
+    let l4 = |x: (f64, f64, f64, f64), y: (f64, f64, f64, f64)| (l(x.0, y.0), l(x.1, y.1), l(x.2, y.2), l(x.3, y.3));
+    let (p, q) = (&a.palette, &b.palette);
+    Some(Keyframe {
+        time,
+        coefs: lv(&a.coefs, &b.coefs),
+        init: lv(&a.init, &b.init),
+        palette: Palette {
+            r: l4(p.r, q.r),
+            g: l4(p.g, q.g),
+            b: l4(p.b, q.b),
+            colver1: l(p.colver1, q.colver1),
+            colver2: l(p.colver2, q.colver2),
+            brightness1: l(p.brightness1, q.brightness1),
+            brightness2: l(p.brightness2, q.brightness2),
+        },
+    })
+}
+
+// path of frame `i` of a sequence written to `path`, e.g. out.png -> out_0007.png
+pub fn frame_path(path: &Path, i: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    path.with_file_name(format!("{}_{:04}.png", stem, i))
+}
+
+enum FrameWriter {
+    Sequence(PathBuf),
+    Gif(GifEncoder<BufWriter<File>>),
+    Apng(png::Writer<BufWriter<File>>),
+}
+
+impl FrameWriter {
+    fn new(path: &Path, frames: usize, opts: &AnimationOptions) -> Result<Self> {
+        Ok(match opts.format {
+            AnimationFormat::PngSequence => FrameWriter::Sequence(path.to_path_buf()),
+            AnimationFormat::Gif => {
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+                encoder.set_repeat(Repeat::Infinite)?;
+                FrameWriter::Gif(encoder)
+            },
+            AnimationFormat::Apng => {
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = png::Encoder::new(file, opts.width as u32, opts.height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                // played forever
+                encoder.set_animated(frames as u32, 0)?;
+                encoder.set_frame_delay(1, opts.fps.max(1))?;
+                FrameWriter::Apng(encoder.write_header()?)
+            },
+        })
+    }
+    fn write<A: Attractor + ?Sized>(
+        &mut self, i: usize, img: DynamicImage, at: &A, plt: &Palette, opts: &AnimationOptions
+    ) -> Result<()> {
+        match self {
+            FrameWriter::Sequence(path) => params::save_png(&img, &frame_path(path, i), at, plt, Some(opts.num_iter))?,
+            FrameWriter::Gif(encoder) => {
+                let delay = Delay::from_numer_denom_ms(1000, opts.fps.max(1) as u32);
+                encoder.encode_frame(Frame::from_parts(img.to_rgba8(), 0, 0, delay))?;
+            },
+            FrameWriter::Apng(writer) => writer.write_image_data(img.to_rgb8().as_raw())?,
+        }
+        Ok(())
+    }
+    fn finish(self) -> Result<()> {
+        if let FrameWriter::Apng(writer) = self {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+// renders the frames of the keyframes of `at` one by one and writes them to `path`, returns the
+// number of frames written. all frames share one viewport holding every keyframe, so the view stays
+// put. a GIF or APNG left unfinished by an error or cancel is removed
+pub fn render_animation<A: Attractor + ?Sized>(
+    at: &mut A, keys: &[Keyframe], opts: &AnimationOptions, path: &Path, progress: &Progress
+) -> Result<usize> {
+    let mut keys = keys.to_vec();
+    keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+    let (n, dim) = (at.coefs().len(), at.state().get_init_val().len());
+    if keys.is_empty() {
+        return Err(anyhow!("no keyframes"));
+    }
+    if keys.iter().any(|k| k.coefs.len() != n || k.init.len() != dim) {
+        return Err(anyhow!("the keyframes do not all belong to {}", at.name()));
+    }
+    let frames = opts.frames.max(1);
+    progress.total.store(frames, Ordering::Relaxed);
+    progress.done.store(0, Ordering::Relaxed);
+
+    let viewport = render::fit_viewport_all(at, keys.len(), opts.num_iter, opts.width, opts.height, |at, i| {
+        at.coefs_mut().copy_from_slice(&keys[i].coefs);
+        at.state_mut().get_init_val_mut().copy_from_slice(&keys[i].init);
+    }).ok_or_else(|| anyhow!("{} cannot be iterated", at.name()))?;
+
+    let mut writer = FrameWriter::new(path, frames, opts)?;
+    let mut write_frames = || {
+        for i in 0..frames {
+            if progress.is_cancelled() {return Err(anyhow!("cancelled"));}
+            let time = if frames > 1 {i as f64 / (frames - 1) as f64} else {0.0};
+            let Some(key) = interpolate(&keys, time, opts.easing) else {break;};
+            at.coefs_mut().copy_from_slice(&key.coefs);
+            at.state_mut().get_init_val_mut().copy_from_slice(&key.init);
+            at.param_changed(true);
+            let hist = render::gen_hist_in(at, viewport, opts.num_iter);
+            let img = render::colorize(&hist, opts.num_iter, opts.width, opts.height, &key.palette);
+            writer.write(i, img, at, &key.palette, opts)?;
+            progress.done.store(i + 1, Ordering::Relaxed);
+        }
+        Ok(())
+    };
+    let written = write_frames().and_then(|_| writer.finish());
+    if written.is_err() && opts.format != AnimationFormat::PngSequence {
+        let _ = fs::remove_file(path);
+    }
+    written.map(|_| frames)
+}
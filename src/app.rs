@@ -1,36 +1,189 @@
-use crate::attractors::{Attractor, Trigonometric, Clifford, Quadratic, Symmetric, Polar, Duffing, Lorenz, DoublePendulum, Custom};
+mod history;
+mod favorites;
+
+use crate::attractors::{Attractor, Trigonometric, Custom};
+use crate::camera::Camera;
+use crate::integrator::Integrator;
+use crate::render::poincare::Section;
 use crate::util;
+use crate::params::{self, System};
+use crate::render;
+use crate::analysis;
+use crate::export;
+use crate::animation;
 use image::{EncodableLayout, DynamicImage};
-use serde_json::{Value};
 use anyhow::{Result, anyhow};
 use std::time;
-use std::path::PathBuf;
-use std::fs;
-use std::io::{BufWriter, Write, BufReader};
-
-#[derive(Debug, PartialEq)]
-enum Enum {
-    Trigonometric,
-    Clifford,
-    Quadratic,
-    Symmetric,
-    Polar,
-    Duffing,
-    Lorenz,
-    DoublePendulum,
-    Custom,
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::f64::consts::{PI, FRAC_PI_2};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RenderMode {
+    Trajectory,
+    Section,
+    Basin,
+}
+
+// settings of one image in the selected render mode, moved to the worker thread
+#[derive(Clone)]
+enum RenderSpec {
+    Trajectory(usize),
+    Section(render::poincare::PoincareOptions),
+    Basin(render::basin::BasinOptions),
+}
+
+impl RenderSpec {
+    // None when cancelled, `partial` receives the intermediate images of trajectories
+    fn render(
+        &self, at: &mut dyn Attractor, w: usize, h: usize, progress: &render::Progress, plt: &util::Palette,
+        mut partial: impl FnMut(DynamicImage)
+    ) -> Option<Vec<f64>> {
+        match self {
+            RenderSpec::Trajectory(n) => render::gen_hist_progressive(at, *n, w, h, progress, |hist, done| {
+                partial(render::colorize(hist, done, w, h, plt));
+            }),
+            RenderSpec::Section(opts) => render::poincare::gen_section(at, opts, w, h, progress),
+            RenderSpec::Basin(opts) => render::basin::gen_basin(at, opts, w, h, progress),
+        }
+    }
+    fn colorize(&self, hist: &[f64], w: usize, h: usize, plt: &util::Palette) -> DynamicImage {
+        match self {
+            RenderSpec::Trajectory(n) => render::colorize(hist, *n, w, h, plt),
+            RenderSpec::Section(opts) => render::colorize(hist, opts.points, w, h, plt),
+            RenderSpec::Basin(_) => render::basin::colorize_levels(hist, w, h, plt),
+        }
+    }
+}
+
+const SIZE_PRESETS: [(usize, usize); 7] = [
+    (512, 512), (1024, 1024), (2048, 2048), (1920, 1080), (3840, 2160), (2160, 3840), (8000, 8000),
+];
+const MAX_SIZE: usize = 16000;
+const PREVIEW_SIZE: usize = 512;
+// larger images are shrunk before they are uploaded as textures
+const MAX_TEXTURE_SIZE: u32 = 4096;
+const BIFURCATION_SIZE: (usize, usize) = (800, 400);
+const PARAMETER_PLANE_SIZE: (usize, usize) = (320, 320);
+// longest side of the previews kept with the history
+const THUMBNAIL_SIZE: u32 = 64;
+const FAVORITES_COLUMNS: usize = 4;
+
+enum JobUpdate {
+    Partial(egui::ColorImage),
+    Done(Vec<f64>),
+}
+
+// high resolution image rendered on a worker thread
+struct HighResJob {
+    progress: Arc<render::Progress>,
+    receiver: mpsc::Receiver<JobUpdate>,
+    start: time::Instant,
+    spec: RenderSpec,
+    size: (usize, usize),
+}
+
+// analysis running on a worker thread, the result is None when it was cancelled
+struct Task<T> {
+    progress: Arc<render::Progress>,
+    receiver: mpsc::Receiver<Option<T>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    fn spawn(ctx: &egui::Context, job: impl FnOnce(&render::Progress) -> Option<T> + Send + 'static) -> Self {
+        let progress = Arc::new(render::Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let (worker_progress, ctx) = (progress.clone(), ctx.clone());
+        thread::spawn(move || {
+            let _ = sender.send(job(&worker_progress));
+            ctx.request_repaint();
+        });
+        Self { progress, receiver }
+    }
+    // Some once the task has ended, the result inside is None if it was cancelled
+    fn poll(&self, ctx: &egui::Context) -> Option<Option<T>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(time::Duration::from_millis(100));
+                None
+            },
+            Err(mpsc::TryRecvError::Disconnected) => Some(None),
+        }
+    }
+    fn cancel(&self) {
+        self.progress.cancel();
+    }
+}
+
+// a progress bar with a cancel button, true when cancel was clicked
+fn task_progress<T>(ui: &mut egui::Ui, task: &Task<T>) -> bool {
+    ui.horizontal(|ui| {
+        ui.add(egui::ProgressBar::new(task.progress.fraction()).show_percentage().desired_width(200.0));
+        ui.add(egui::Button::new("Cancel")).clicked()
+    }).inner
+}
+
+// animation written on a worker thread
+struct AnimationJob {
+    progress: Arc<render::Progress>,
+    receiver: mpsc::Receiver<Result<usize>>,
+    path: PathBuf,
 }
 
 pub struct MyApp {
     num_iter_low: usize,
     num_iter_high: usize,
+    width: usize,
+    height: usize,
+    scale_iter: bool,
     attractor: Box<dyn Attractor>,
-    selected_attractor: Enum,
+    selected_attractor: System,
     palette: util::Palette,
     open_window: bool,
     tex_handle_pre: Option<egui::TextureHandle>,
     tex_handle_high: Option<egui::TextureHandle>,
     elapsed: time::Duration,
+    job: Option<HighResJob>,
+    lyapunov: Option<Vec<f64>>,
+    lyapunov_task: Option<Task<Vec<f64>>>,
+    // correlation and box-counting curves
+    dimension: Option<(analysis::Scaling, analysis::Scaling)>,
+    dimension_task: Option<Task<(analysis::Scaling, analysis::Scaling)>>,
+    search_status: String,
+    // coefficients found for the system of the given name, None if nothing was found
+    search_task: Option<Task<(String, Option<analysis::search::Candidate>)>>,
+    render_mode: RenderMode,
+    poincare: Option<render::poincare::PoincareOptions>,
+    // the section of the current system being searched
+    poincare_task: Option<Task<render::poincare::PoincareOptions>>,
+    basin: Option<render::basin::BasinOptions>,
+    open_bifurcation: bool,
+    bifurcation: Option<render::bifurcation::BifurcationOptions>,
+    tex_handle_bif: Option<egui::TextureHandle>,
+    // the histogram of the diagram being generated with its samples per column
+    bifurcation_task: Option<Task<(Vec<f64>, usize)>>,
+    open_plane: bool,
+    plane: Option<analysis::PlaneOptions>,
+    // options of the image shown, clicks are mapped with these
+    plane_shown: Option<analysis::PlaneOptions>,
+    tex_handle_plane: Option<egui::TextureHandle>,
+    // the values of the plane being generated with its options
+    plane_task: Option<Task<(Vec<f64>, analysis::PlaneOptions)>>,
+    export: export::ExportOptions,
+    history: history::History,
+    // basin preview being drawn, with the history key of its state
+    preview_task: Option<Task<(history::Key, DynamicImage)>>,
+    // small copy of the last preview with the history key of its state
+    thumbnail: Option<(history::Key, DynamicImage)>,
+    favorites: Vec<favorites::Favorite>,
+    open_favorites: bool,
+    keyframes: Vec<animation::Keyframe>,
+    animation: animation::AnimationOptions,
+    open_animation: bool,
+    animation_job: Option<AnimationJob>,
+    animation_status: String,
     expr_str: [String; 2],
     custom_status: String,
 }
@@ -40,13 +193,47 @@ impl Default for MyApp {
         Self {
             num_iter_low: 100000,
             num_iter_high: 10000000,
+            width: 1024,
+            height: 1024,
+            scale_iter: true,
             attractor: Box::<Trigonometric>::default(),
-            selected_attractor: Enum::Trigonometric,
+            selected_attractor: System::Trigonometric,
             palette: util::Palette::default(),
             open_window: false,
             tex_handle_pre: None,
             tex_handle_high: None,
             elapsed: time::Duration::new(0, 0),
+            job: None,
+            lyapunov: None,
+            dimension: None,
+            search_status: "".to_string(),
+            search_task: None,
+            lyapunov_task: None,
+            dimension_task: None,
+            render_mode: RenderMode::Trajectory,
+            poincare: None,
+            poincare_task: None,
+            basin: None,
+            open_bifurcation: false,
+            bifurcation: None,
+            tex_handle_bif: None,
+            bifurcation_task: None,
+            open_plane: false,
+            plane: None,
+            plane_shown: None,
+            tex_handle_plane: None,
+            plane_task: None,
+            export: export::ExportOptions::default(),
+            history: history::History::default(),
+            preview_task: None,
+            thumbnail: None,
+            favorites: vec![],
+            open_favorites: false,
+            keyframes: vec![],
+            animation: animation::AnimationOptions::default(),
+            open_animation: false,
+            animation_job: None,
+            animation_status: "".to_string(),
             expr_str: ["a0 * sin(a1 * y) + a2 * cos(a3 * x)".to_string(), "a4 * sin(a5 * x) + a6 * cos(a7 * y)".to_string()],
             custom_status: "".to_string()
         }
@@ -54,86 +241,353 @@ impl Default for MyApp {
 }
 
 impl MyApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Default::default()
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            app.favorites = favorites::load(storage);
+        }
+        app
     }
     
     fn set_attractor(&mut self, at: Box<dyn Attractor> ) {
         self.attractor = at;
+        if let Some(system) = System::from_name(self.attractor.name()) {
+            self.selected_attractor = system;
+        }
+        // a custom attractor has its expressions compiled from map_str already, when it was loaded
+        // or created, so the fields show them as applied
+        if self.selected_attractor == System::Custom {
+            self.expr_str = match self.attractor.map_str().split_once(';') {
+                Some((x, y)) => [x.to_string(), y.to_string()],
+                None => [self.attractor.map_str().to_string(), String::new()],
+            };
+            self.custom_status = "OK.".to_string();
+        }
+        self.clear_analyses();
+        self.poincare = None;
+        if let Some(task) = self.poincare_task.take() {
+            task.cancel();
+        }
+        self.basin = None;
+        self.plane = None;
+        self.plane_shown = None;
+        // the coefficients of a running plane belong to the previous system
+        if let Some(task) = self.plane_task.take() {
+            task.cancel();
+        }
     }
 
-    fn save_params(&self, path: &PathBuf) -> std::io::Result<()> {
-        let file = fs::File::create(path)?;
-        let mut writer = BufWriter::new(file);
-        serde_json::to_writer(&mut writer, &self.attractor)?;
-        writer.flush()?;
+    fn render_settings(&self) -> params::RenderSettings {
+        params::RenderSettings {
+            num_iter_low: self.num_iter_low,
+            num_iter_high: self.num_iter_high,
+            scale_iter: self.scale_iter,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    fn save_params(&self, path: &Path) -> Result<()> {
+        params::save_document(path, self.attractor.as_ref(), Some(&self.palette), Some(&self.render_settings()))
+    }
+
+    // a PNG also restores the palette, iteration count and size it was saved with
+    fn load_params(&mut self, path: &Path) -> Result<()> {
+        if !params::is_png(path) {
+            let document = params::load_document(path)?;
+            self.set_attractor(document.attractor);
+            if let Some(palette) = document.palette {
+                self.palette = palette;
+            }
+            if let Some(render) = document.render {
+                self.num_iter_low = render.num_iter_low;
+                self.num_iter_high = render.num_iter_high;
+                self.scale_iter = render.scale_iter;
+                (self.width, self.height) = (render.width, render.height);
+            }
+            return Ok(());
+        }
+        let image = params::load_png_params(path)?;
+        self.set_attractor(image.attractor);
+        if let Some(palette) = image.palette {
+            self.palette = palette;
+        }
+        if let Some(n) = image.num_iter {
+            self.num_iter_high = n;
+            self.scale_iter = false;
+        }
+        if let Some((w, h)) = image.size {
+            (self.width, self.height) = (w, h);
+        }
         Ok(())
     }
 
-    fn load_params(&mut self, path: &PathBuf) -> Result<()> {
-        let file = fs::File::open(path)?;
-        let reader = BufReader::new(file);
-        let de: Value = serde_json::from_reader(reader)?;
-        if let Some(Value::String(name)) = de.get("name") {
-            match name.as_str() {
-                "Trigonometric Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Trigonometric>(de)?);
-                },
-                "Clifford Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Clifford>(de)?);
-                },
-                "Quadratic Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Quadratic>(de)?);
-                },
-                "Symmetric Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Symmetric>(de)?);
-                },
-                "Polar Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Polar>(de)?);
-                },
-                "Lorenz Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Lorenz>(de)?);
+    // preview keeps the aspect ratio of the output image
+    fn preview_size(&self) -> (usize, usize) {
+        if self.width >= self.height {
+            (PREVIEW_SIZE, (PREVIEW_SIZE * self.height / self.width).max(1))
+        }
+        else {
+            ((PREVIEW_SIZE * self.width / self.height).max(1), PREVIEW_SIZE)
+        }
+    }
+
+    // the sliders give the iterations for 512x512 and 1024x1024
+    fn preview_iter(&self) -> usize {
+        let (w, h) = self.preview_size();
+        if self.scale_iter {render::scaled_iters(self.num_iter_low * 4, w, h)} else {self.num_iter_low}
+    }
+    fn high_res_iter(&self) -> usize {
+        if self.scale_iter {render::scaled_iters(self.num_iter_high, self.width, self.height)} else {self.num_iter_high}
+    }
+
+    // sections need a flow, the trajectory is drawn otherwise
+    fn render_spec(&self, preview: bool) -> RenderSpec {
+        let is_flow = self.attractor.state().get_dt().is_some();
+        match (self.render_mode, &self.poincare, &self.basin) {
+            // a tenth of the points of the high resolution image
+            (RenderMode::Section, Some(opts), _) if is_flow => {
+                let mut opts = opts.clone();
+                if preview {
+                    opts.points = (opts.points / 10).max(1);
+                    opts.max_iters /= 10;
+                }
+                RenderSpec::Section(opts)
+            },
+            (RenderMode::Basin, _, Some(opts)) => RenderSpec::Basin(opts.clone()),
+            _ => RenderSpec::Trajectory(if preview {self.preview_iter()} else {self.high_res_iter()}),
+        }
+    }
+
+    fn update_preview(&mut self, ctx: &egui::Context) {
+        self.attractor.param_changed(true);
+        // a newer preview replaces the one being drawn
+        if let Some(task) = self.preview_task.take() {
+            task.cancel();
+        }
+        let (w, h) = self.preview_size();
+        let key = history::Key::new(self.attractor.as_ref(), &self.palette);
+        let image = match self.render_spec(true) {
+            RenderSpec::Trajectory(n) => self.attractor.gen_img(n, w, h, &self.palette),
+            // sections may take long to collect their crossings and every pixel of a basin is a whole
+            // orbit, they are drawn in the background, basins at a quarter of the size
+            spec => {
+                let (mut attractor, palette) = (self.attractor.clone_box(), self.palette.clone());
+                let scale = if matches!(spec, RenderSpec::Basin(_)) {4} else {1};
+                self.preview_task = Some(Task::spawn(ctx, move |progress| {
+                    let (bw, bh) = ((w / scale).max(1), (h / scale).max(1));
+                    let hist = spec.render(attractor.as_mut(), bw, bh, progress, &palette, |_| {})?;
+                    let image = spec.colorize(&hist, bw, bh, &palette)
+                        .resize_exact(w as u32, h as u32, image::imageops::FilterType::Nearest);
+                    Some((key, image))
+                }));
+                return;
+            },
+        };
+        self.show_preview(ctx, key, image);
+    }
+
+    fn show_preview(&mut self, ctx: &egui::Context, key: history::Key, image: DynamicImage) {
+        self.thumbnail = Some((key, image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)));
+        self.tex_handle_pre = Some(ctx.load_texture("pre_image", image2texture(image), Default::default()));
+    }
+
+    fn poll_preview(&mut self, ctx: &egui::Context) {
+        let Some(result) = self.preview_task.as_ref().and_then(|task| task.poll(ctx)) else {return;};
+        self.preview_task = None;
+        if let Some((key, image)) = result {
+            self.show_preview(ctx, key, image);
+        }
+    }
+
+    // the cached analyses and images are outdated after the coefficients were set from outside the panel
+    fn coefs_changed(&mut self, ctx: &egui::Context) {
+        self.clear_analyses();
+        if !self.open_window {
+            self.update_preview(ctx);
+        }
+        else {
+            self.attractor.param_changed(true);
+        }
+    }
+
+    // a running analysis belongs to the previous coefficients
+    fn clear_analyses(&mut self) {
+        self.lyapunov = None;
+        self.dimension = None;
+        if let Some(task) = self.lyapunov_task.take() {
+            task.cancel();
+        }
+        if let Some(task) = self.dimension_task.take() {
+            task.cancel();
+        }
+    }
+
+    // show a snapshot of the history
+    fn restore(&mut self, ctx: &egui::Context, attractor: Box<dyn Attractor>, palette: util::Palette) {
+        if attractor.name() != self.attractor.name() {
+            self.set_attractor(attractor);
+        }
+        else {
+            self.attractor = attractor;
+        }
+        self.palette = palette;
+        self.clear_analyses();
+        if !self.open_window {
+            self.update_preview(ctx);
+        }
+        else {
+            self.attractor.param_changed(true);
+        }
+    }
+
+    // the cached image of the last high resolution render, trajectories are generated if there is none
+    fn save_image(&mut self, path: &Path) -> Result<()> {
+        let (w, h) = (self.width, self.height);
+        let spec = self.render_spec(false);
+        if !self.attractor.is_param_changed() && self.attractor.img_vec_mut().len() == w * h {
+            let img = spec.colorize(self.attractor.img_vec_mut(), w, h, &self.palette);
+            if !params::is_png(path) {
+                img.save(path)?;
+                return Ok(());
+            }
+            let n = match spec {
+                RenderSpec::Trajectory(n) => Some(n),
+                _ => None,
+            };
+            return params::save_png(&img, path, self.attractor.as_ref(), &self.palette, n);
+        }
+        match spec {
+            RenderSpec::Trajectory(n) => self.attractor.save_img(path, n, w, h, &self.palette)?,
+            _ => return Err(anyhow!("the image has not been generated")),
+        }
+        Ok(())
+    }
+
+    fn start_high_res(&mut self, ctx: &egui::Context) {
+        self.cancel_high_res();
+        let spec = self.render_spec(false);
+        let (w, h) = (self.width, self.height);
+        // only the palette changed, recolor the cached histogram
+        if !self.attractor.is_param_changed() && self.attractor.img_vec_mut().len() == w * h {
+            let start = time::Instant::now();
+            let image = image2texture(spec.colorize(self.attractor.img_vec_mut(), w, h, &self.palette));
+            self.elapsed = start.elapsed();
+            self.tex_handle_high = Some(ctx.load_texture("high_image", image, Default::default()));
+            return;
+        }
+
+        let mut attractor = self.attractor.clone_box();
+        let palette = self.palette.clone();
+        let progress = Arc::new(render::Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let (worker_progress, worker_spec, ctx) = (progress.clone(), spec.clone(), ctx.clone());
+        thread::spawn(move || {
+            let hist = worker_spec.render(attractor.as_mut(), w, h, &worker_progress, &palette, |image| {
+                let _ = sender.send(JobUpdate::Partial(image2texture(image)));
+                ctx.request_repaint();
+            });
+            if let Some(hist) = hist {
+                let _ = sender.send(JobUpdate::Done(hist));
+            }
+            ctx.request_repaint();
+        });
+        self.attractor.param_changed(false);
+        self.job = Some(HighResJob {
+            progress,
+            receiver,
+            start: time::Instant::now(),
+            spec,
+            size: (w, h),
+        });
+    }
+
+    fn cancel_high_res(&mut self) {
+        if let Some(job) = self.job.take() {
+            job.progress.cancel();
+            // the cached histogram was not updated
+            self.attractor.param_changed(true);
+        }
+    }
+
+    fn poll_high_res(&mut self, ctx: &egui::Context) {
+        let Some(job) = &self.job else {return;};
+        loop {
+            match job.receiver.try_recv() {
+                Ok(JobUpdate::Partial(image)) => {
+                    self.tex_handle_high = Some(ctx.load_texture("high_image", image, Default::default()));
                 },
-                "Duffing Attractor" => {
-                    self.attractor = Box::new(serde_json::from_value::<Duffing>(de)?);
+                Ok(JobUpdate::Done(hist)) => {
+                    let (w, h) = job.size;
+                    self.elapsed = job.start.elapsed();
+                    let image = image2texture(job.spec.colorize(&hist, w, h, &self.palette));
+                    self.tex_handle_high = Some(ctx.load_texture("high_image", image, Default::default()));
+                    if !self.attractor.is_param_changed() {
+                        *self.attractor.img_vec_mut() = hist;
+                    }
+                    self.job = None;
+                    break;
                 },
-                "DoublePendulum" => {
-                    self.attractor = Box::new(serde_json::from_value::<DoublePendulum>(de)?);
+                Err(mpsc::TryRecvError::Empty) => {
+                    ctx.request_repaint_after(time::Duration::from_millis(100));
+                    break;
                 },
-                "Custom" => {
-                    //self.attractor = Box::new(serde_json::from_value::<DoublePendulum>(de)?);
-                    if let Some(Value::String(map_str)) = de.get("map_str") {
-                        let custom = Custom::new(map_str);
-                        match custom {
-                            Ok(custom) => {
-                                self.attractor = Box::new(custom);
-                                self.custom_status = "OK.".to_string();
-                            }
-                            Err(e) => {
-                                self.custom_status = format!("NG: {:?}", e);
-                            }
-                        }
-                    }
-                    else {
-                        self.custom_status = format!("NG: No map_str");
-                    }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.cancel_high_res();
+                    break;
                 },
-                _ => {
-                    return Err(anyhow!("Invald Attractor name {}.", name.clone()));
-                }
             }
         }
-        else {
-            return Err(anyhow!(" Attractor name NotFound."));
+    }
+
+    fn start_animation(&mut self, ctx: &egui::Context, path: PathBuf) {
+        let mut attractor = self.attractor.clone_box();
+        let (keys, opts) = (self.keyframes.clone(), self.animation.clone());
+        let progress = Arc::new(render::Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let (worker_progress, worker_path, ctx) = (progress.clone(), path.clone(), ctx.clone());
+        thread::spawn(move || {
+            let result = animation::render_animation(attractor.as_mut(), &keys, &opts, &worker_path, &worker_progress);
+            let _ = sender.send(result);
+            ctx.request_repaint();
+        });
+        self.animation_status = "".to_string();
+        self.animation_job = Some(AnimationJob { progress, receiver, path });
+    }
+
+    fn poll_animation(&mut self, ctx: &egui::Context) {
+        let Some(job) = &self.animation_job else {return;};
+        match job.receiver.try_recv() {
+            Ok(result) => {
+                self.animation_status = match result {
+                    Ok(frames) => format!("{} frames written to {:?}", frames, job.path.file_name().unwrap_or_default()),
+                    Err(e) => format!("Failed to render: {:#}", e),
+                };
+                self.animation_job = None;
+            },
+            Err(mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(time::Duration::from_millis(100));
+            },
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.animation_job = None;
+            },
         }
-        Ok(())
     }
 }
 
 impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        favorites::save(storage, &self.favorites);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_high_res(ctx);
+        self.poll_animation(ctx);
+        self.poll_preview(ctx);
         let mut param_changed = false;
         let mut color_changed = false;
+        // snapshot of the history to show
+        let mut jump = None;
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 let is_web = cfg!(target_arch = "wasm32");
@@ -156,47 +610,14 @@ impl eframe::App for MyApp {
                 egui::ComboBox::from_label("Select Attractor")
                 .selected_text(format!("{:?}", self.selected_attractor))
                 .show_ui(ui, |ui| {
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Trigonometric, "Trigonometric").clicked() {
-                        self.set_attractor(Box::<Trigonometric>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Clifford, "Clifford").clicked() {
-                        self.set_attractor(Box::<Clifford>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Quadratic, "Quadratic").clicked() {
-                        self.set_attractor(Box::<Quadratic>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Symmetric, "Symmetric").clicked() {
-                        self.set_attractor(Box::<Symmetric>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Polar, "Polar").clicked() {
-                        self.set_attractor(Box::<Polar>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Duffing, "Duffing").clicked() {
-                        self.set_attractor(Box::<Duffing>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Lorenz, "Lorentz").clicked() {
-                        self.set_attractor(Box::<Lorenz>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::DoublePendulum, "DoublePendulum").clicked() {
-                        self.set_attractor(Box::<DoublePendulum>::default());
-                        param_changed |= true;
-                    }
-                    if ui.selectable_value(&mut self.selected_attractor, Enum::Custom, "Custom").clicked() {
-                        self.set_attractor(Box::<Custom>::default());
-                        let arr: Vec<&str> = self.attractor.map_str().split(";").collect();
-                        self.expr_str = [arr[0].to_string(), arr[1].to_string()];
-                        param_changed |= true;
-                        
+                    for system in System::ALL {
+                        if ui.selectable_value(&mut self.selected_attractor, system, format!("{:?}", system)).clicked() {
+                            self.set_attractor(system.default_attractor());
+                            param_changed |= true;
+                        }
                     }
                 });
-                if self.selected_attractor == Enum::Custom {
+                if self.selected_attractor == System::Custom {
                     ui.label("x:");
                     ui.text_edit_singleline(&mut self.expr_str[0]);
                     ui.label("y:");
@@ -206,9 +627,9 @@ impl eframe::App for MyApp {
                         let custom = Custom::new(&self.expr_str.join(";"));
                         match custom {
                             Ok(custom) => {
-                                self.attractor = Box::new(custom);
+                                // the options of the analyses belong to the previous expressions
+                                self.set_attractor(Box::new(custom));
                                 param_changed |= true;
-                                self.custom_status = "OK.".to_string();
                             }
                             Err(e) => {
                                 self.custom_status = format!("NG: {:?}", e);
@@ -263,6 +684,34 @@ impl eframe::App for MyApp {
                 color_changed |= ui.add(egui::DragValue::new(&mut self.palette.brightness1).clamp_range(0.0..=2.0).fixed_decimals(2).speed(0.01).prefix("value1: ")).changed();
                 color_changed |= ui.add(egui::DragValue::new(&mut self.palette.brightness2).clamp_range(1.0..=100.0).fixed_decimals(1).speed(0.2).prefix("value2: ")).changed();
             });
+
+            ui.separator();
+            ui.label("history");
+            ui.horizontal(|ui| {
+                if ui.add_enabled(self.history.can_undo(), egui::Button::new("Undo")).clicked() {
+                    jump = Some(self.history.current() - 1);
+                }
+                if ui.add_enabled(self.history.can_redo(), egui::Button::new("Redo")).clicked() {
+                    jump = Some(self.history.current() + 1);
+                }
+            });
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // newest first
+                for (i, snapshot) in self.history.entries().iter().enumerate().rev() {
+                    let response = ui.horizontal(|ui| {
+                        if let Some(handle) = &snapshot.thumbnail {
+                            ui.add(egui::Image::from_texture(
+                                egui::load::SizedTexture::new(handle.id(), handle.size_vec2())
+                            ).max_size(egui::vec2(48.0, 48.0)));
+                        }
+                        ui.selectable_label(i == self.history.current(), format!("{}: {}", i + 1, snapshot.attractor.name()))
+                    }).inner;
+                    let coefs = snapshot.attractor.coefs().iter().map(|c| format!("{:.3}", c)).collect::<Vec<_>>();
+                    if response.on_hover_text(coefs.join(", ")).clicked() {
+                        jump = Some(i);
+                    }
+                }
+            });
         });
 
         // todo: add fix zero
@@ -302,22 +751,96 @@ impl eframe::App for MyApp {
                         .clamp_range(0.0..=dt_range)
                         .fixed_decimals(5)
                         .speed(dt_range*0.01)
-                        .prefix(format!("dt: "))
+                        .prefix("dt: ")
                     ).changed();
                 } 
             }
+            if self.attractor.state().get_dt().is_some() {
+                let mut integrator = self.attractor.state().integrator();
+                let separable = self.attractor.separable();
+                // the coefficients may have left the separable case since leapfrog was picked
+                if integrator == Integrator::Leapfrog && !separable {
+                    integrator = Integrator::Rk4;
+                }
+                egui::ComboBox::from_label("integrator")
+                .selected_text(integrator.name())
+                .show_ui(ui, |ui| {
+                    let mut choices = vec![Integrator::Euler, Integrator::Rk4, Integrator::rk45()];
+                    if separable {
+                        choices.push(Integrator::Leapfrog);
+                    }
+                    for choice in choices {
+                        // keep the tolerances when RK45 is selected again
+                        let selected = integrator.name() == choice.name();
+                        if ui.selectable_label(selected, choice.name()).clicked() && !selected {
+                            integrator = choice;
+                        }
+                    }
+                });
+                if let Integrator::Rk45 { rtol, atol } = &mut integrator {
+                    ui.horizontal(|ui|{
+                        ui.add(egui::DragValue::new(rtol).clamp_range(1e-12..=1e-2).speed(1e-7).custom_formatter(|v, _| format!("{:.0e}", v)).prefix("rtol: "));
+                        ui.add(egui::DragValue::new(atol).clamp_range(1e-15..=1e-2).speed(1e-10).custom_formatter(|v, _| format!("{:.0e}", v)).prefix("atol: "));
+                    });
+                }
+                if integrator != self.attractor.state().integrator() {
+                    self.attractor.state_mut().set_integrator(integrator);
+                    changed_left |= true;
+                }
+            }
+
+            if self.attractor.state().get_xs().len() >= 3 {
+                ui.separator();
+                let camera = self.attractor.state_mut().camera_mut();
+                let mut enabled = camera.is_some();
+                if ui.checkbox(&mut enabled, "3D camera").changed() {
+                    *camera = if enabled {Some(Camera::default())} else {None};
+                    changed_left |= true;
+                }
+                if let Some(camera) = camera {
+                    changed_left |= ui.add(
+                        egui::Slider::new(&mut camera.yaw, -PI..=PI).text("yaw")
+                    ).changed();
+                    changed_left |= ui.add(
+                        egui::Slider::new(&mut camera.pitch, -FRAC_PI_2..=FRAC_PI_2).text("pitch")
+                    ).changed();
+                    changed_left |= ui.add(
+                        egui::Slider::new(&mut camera.zoom, 0.1..=10.0).logarithmic(true).text("zoom")
+                    ).changed();
+                    changed_left |= ui.checkbox(&mut camera.perspective, "perspective").changed();
+                }
+            }
             
             ui.separator();
-            if ui.add(egui::Button::new("Randomize")).clicked() {
-                self.attractor.change_random_coefs();
-                changed_left |= true;
+            ui.horizontal(|ui|{
+                if ui.add(egui::Button::new("Randomize")).clicked() {
+                    self.attractor.change_random_coefs();
+                    changed_left |= true;
+                }
+                if self.search_task.is_none() && ui.add(egui::Button::new("Find chaotic")).clicked() {
+                    let mut attractor = self.attractor.clone_box();
+                    self.search_status = "".to_string();
+                    self.search_task = Some(Task::spawn(ctx, move |progress| {
+                        let found = analysis::find_chaotic(attractor.as_mut(), &analysis::SearchOptions::default(), progress);
+                        if progress.is_cancelled() {return None;}
+                        Some((attractor.name().to_string(), found))
+                    }));
+                }
+            });
+            if let Some(task) = &self.search_task {
+                if task_progress(ui, task) {
+                    task.cancel();
+                }
+            }
+            if !self.search_status.is_empty() {
+                ui.label(&self.search_status);
             }
             
             ui.add_space(5.0);
             
             let ranges = self.attractor.coef_ranges();
             let speeds = self.attractor.speeds();
-            for (i,(coef, (range, speed))) in self.attractor.coefs_mut().iter_mut().zip(ranges.into_iter().zip(speeds.into_iter())).enumerate() {
+            for (i,(coef, (range, speed))) in self.attractor.coefs_mut().iter_mut().zip(ranges.into_iter().zip(speeds)).enumerate() {
                 changed_left |= ui.add(
                     egui::DragValue::new(coef)
                     .clamp_range(range)
@@ -328,6 +851,71 @@ impl eframe::App for MyApp {
             }
 
             ui.separator();
+            if self.lyapunov_task.is_none() && ui.add(egui::Button::new("Lyapunov exponents")).clicked() {
+                let mut attractor = self.attractor.clone_box();
+                let (n, skip) = analysis::lyapunov::default_iters(attractor.as_ref());
+                let dim = attractor.state().get_xs().len();
+                self.lyapunov_task = Some(Task::spawn(ctx, move |progress| {
+                    analysis::lyapunov_spectrum(attractor.as_mut(), n, skip, dim, progress)
+                }));
+            }
+            if let Some(task) = &self.lyapunov_task {
+                if task_progress(ui, task) {
+                    task.cancel();
+                }
+            }
+            if let Some(exponents) = &self.lyapunov {
+                for (i, l) in exponents.iter().enumerate() {
+                    ui.label(format!("λ{}: {:.4}", i + 1, l));
+                }
+                ui.label(match exponents.first() {
+                    Some(l) if l.is_nan() => "diverges",
+                    Some(&l) if l > 0.005 => "chaotic",
+                    Some(&l) if l > -0.005 => "periodic / quasi-periodic",
+                    _ => "fixed point",
+                });
+            }
+
+            if self.dimension_task.is_none() && ui.add(egui::Button::new("Fractal dimension")).clicked() {
+                let mut attractor = self.attractor.clone_box();
+                self.dimension_task = Some(Task::spawn(ctx, move |progress| {
+                    analysis::dimension_scalings(attractor.as_mut(), progress)
+                }));
+            }
+            if let Some(task) = &self.dimension_task {
+                if task_progress(ui, task) {
+                    task.cancel();
+                }
+            }
+            if let Some((correlation, boxes)) = &self.dimension {
+                ui.label(format!("D2 (correlation): {:.3}", correlation.dimension));
+                log_log_plot(ui, correlation, "ln r", "ln C(r)");
+                ui.label(format!("D0 (box counting): {:.3}", boxes.dimension));
+                log_log_plot(ui, boxes, "ln 1/s", "ln N(s)");
+            }
+
+            if ui.add(egui::Button::new("Bifurcation diagram")).clicked() {
+                self.open_bifurcation = true;
+            }
+            if ui.add(egui::Button::new("Parameter plane")).clicked() {
+                self.open_plane = true;
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("★ Favorite")).clicked() {
+                    let thumbnail = self.thumbnail.as_ref()
+                        .filter(|(k, _)| k.matches(self.attractor.as_ref(), &self.palette))
+                        .map(|(_, img)| img.clone());
+                    self.favorites.push(favorites::Favorite::new(self.attractor.as_ref(), &self.palette, thumbnail));
+                }
+                if ui.add(egui::Button::new("Favorites")).clicked() {
+                    self.open_favorites = true;
+                }
+                if ui.add(egui::Button::new("Animation")).clicked() {
+                    self.open_animation = true;
+                }
+            });
             if ui.add(egui::Button::new("Save Params")).clicked() {
                 let dialog = rfd::FileDialog::new()
                     .set_file_name(self.attractor.name().replace(' ', "_"))
@@ -347,7 +935,7 @@ impl eframe::App for MyApp {
             }
             if ui.add(egui::Button::new("Load Params")).clicked() {
                 let dialog = rfd::FileDialog::new()
-                    .add_filter("JSON", &["json"])
+                    .add_filter("JSON or PNG", &["json", "png"])
                     .set_directory("/")
                     .pick_file();
                 if let Some(path) = dialog {
@@ -364,11 +952,82 @@ impl eframe::App for MyApp {
                     }
                 }
             }
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("Export Trajectory")).clicked() {
+                    let dialog = rfd::FileDialog::new()
+                        .set_file_name(self.attractor.name().replace(' ', "_"))
+                        .set_directory("/")
+                        .add_filter("CSV", &["csv"])
+                        .add_filter("NumPy", &["npy"])
+                        .save_file();
+                    if let Some(mut path) = dialog {
+                        match export::ExportFormat::from_path(&path) {
+                            Some(format) => self.export.format = format,
+                            None => {
+                                path.set_extension(self.export.format.extension());
+                            },
+                        }
+                        let mut attractor = self.attractor.clone_box();
+                        let export_result = export::export_trajectory(attractor.as_mut(), &path, &self.export);
+                        rfd::MessageDialog::new()
+                        .set_title("Message")
+                        .set_description(
+                            match export_result {
+                                Ok(rows) => format!("{} rows exported to {:?}", rows, path.file_name().unwrap()),
+                                Err(e) => format!("Failed to export: {:#}", e),
+                            }
+                        )
+                        .set_buttons(rfd::MessageButtons::Ok)
+                        .show();
+                    }
+                }
+                ui.add(egui::DragValue::new(&mut self.export.steps)
+                    .clamp_range(1..=100000000)
+                    .speed(1000)
+                    .prefix("steps: ")
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.export.stride)
+                    .clamp_range(1..=100000)
+                    .prefix("stride: ")
+                );
+                ui.checkbox(&mut self.export.projected, "projected point");
+            });
             param_changed |= changed_left;
         });
 
         if param_changed {
             self.attractor.param_changed(true);
+            self.clear_analyses();
+        }
+        if self.basin.is_none() {
+            self.basin = Some(render::basin::BasinOptions::new(self.attractor.as_ref()));
+        }
+        // the section follows the system and its forcing, the plane of a new system is searched in
+        // the background
+        if self.attractor.state().get_dt().is_some() {
+            let period = self.attractor.forcing_period();
+            match &mut self.poincare {
+                None if self.poincare_task.is_none() => {
+                    let mut attractor = self.attractor.clone_box();
+                    self.poincare_task = Some(Task::spawn(ctx, move |progress| {
+                        render::poincare::PoincareOptions::new(attractor.as_mut(), progress)
+                    }));
+                },
+                None => {},
+                Some(opts) => if let (Section::Stroboscopic { period: p }, Some(period)) = (&mut opts.section, period) {
+                    *p = period;
+                },
+            }
+        }
+        if let Some(result) = self.poincare_task.as_ref().and_then(|task| task.poll(ctx)) {
+            self.poincare_task = None;
+            self.poincare = result;
+            // the preview was drawn as a trajectory until the section was found
+            if self.render_mode == RenderMode::Section && !self.open_window {
+                self.update_preview(ctx);
+            }
         }
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui|{
@@ -380,63 +1039,174 @@ impl eframe::App for MyApp {
                     self.attractor.param_changed(true);
                 }
                 if ui.add(egui::Button::new("Generate")).clicked() {
-                    let start = time::Instant::now();
-                    let image = image2texture(
-                        self.attractor.gen_img(self.num_iter_high, 1024, 1024, &self.palette)
-                    );
-                    self.elapsed = start.elapsed();
-                    self.tex_handle_high = Some(ctx.load_texture("high_image", image, Default::default()));
                     self.open_window = true;
-                    self.attractor.param_changed(false);
+                    self.start_high_res(ctx);
+                }
+            });
+            ui.horizontal(|ui|{
+                let mut size_changed = false;
+                egui::ComboBox::from_id_source("size_preset")
+                .selected_text(format!("{}x{}", self.width, self.height))
+                .show_ui(ui, |ui| {
+                    for (w, h) in SIZE_PRESETS {
+                        if ui.selectable_label((self.width, self.height) == (w, h), format!("{}x{}", w, h)).clicked() {
+                            (self.width, self.height) = (w, h);
+                            size_changed |= true;
+                        }
+                    }
+                });
+                size_changed |= ui.add(
+                    egui::DragValue::new(&mut self.width)
+                    .clamp_range(16..=MAX_SIZE)
+                    .prefix("width: ")
+                ).changed();
+                size_changed |= ui.add(
+                    egui::DragValue::new(&mut self.height)
+                    .clamp_range(16..=MAX_SIZE)
+                    .prefix("height: ")
+                ).changed();
+                size_changed |= ui.checkbox(&mut self.scale_iter, "scale iterations with size").changed();
+                ui.label(format!("{} iterations", self.high_res_iter()));
+                if size_changed {
+                    self.attractor.param_changed(true);
+                    param_changed |= true;
                 }
             });
             
-            if (param_changed || color_changed) && !self.open_window {
+            let mut mode_changed = false;
+            let is_flow = self.attractor.state().get_dt().is_some();
+            let dim = self.attractor.state().get_xs().len();
+            ui.horizontal(|ui|{
+                mode_changed |= ui.radio_value(&mut self.render_mode, RenderMode::Trajectory, "trajectory").changed();
+                if is_flow {
+                    mode_changed |= ui.radio_value(&mut self.render_mode, RenderMode::Section, "Poincaré section").changed();
+                }
+                mode_changed |= ui.radio_value(&mut self.render_mode, RenderMode::Basin, "basin").changed();
+            });
+            if let (RenderMode::Basin, Some(opts)) = (self.render_mode, &mut self.basin) {
+                ui.horizontal(|ui|{
+                    egui::ComboBox::from_id_source("basin_mode")
+                    .selected_text(opts.mode.name())
+                    .show_ui(ui, |ui| {
+                        for mode in render::basin::BasinMode::ALL {
+                            mode_changed |= ui.selectable_value(&mut opts.mode, mode, mode.name()).changed();
+                        }
+                    });
+                    for (axis, label) in opts.axes.iter_mut().zip(["horizontal: x", "vertical: x"]) {
+                        mode_changed |= ui.add(
+                            egui::DragValue::new(axis).clamp_range(0..=dim - 1).prefix(label)
+                        ).changed();
+                    }
+                    mode_changed |= ui.add(
+                        egui::DragValue::new(&mut opts.max_iters).clamp_range(10..=10000000).prefix("max iter: ")
+                    ).changed();
+                });
+                ui.horizontal(|ui|{
+                    for (range, label) in opts.ranges.iter_mut().zip(["horizontal", "vertical"]) {
+                        let (mut start, mut end) = (*range.start(), *range.end());
+                        mode_changed |= ui.add(egui::DragValue::new(&mut start).fixed_decimals(3).speed(0.01).prefix(format!("{} from: ", label))).changed();
+                        mode_changed |= ui.add(egui::DragValue::new(&mut end).fixed_decimals(3).speed(0.01).prefix("to: ")).changed();
+                        *range = start..=end;
+                    }
+                });
+            }
+            if mode_changed {
+                self.cancel_high_res();
                 self.attractor.param_changed(true);
-                let image = image2texture(
-                    self.attractor.gen_img(self.num_iter_low, 512, 512, &self.palette)
-                );
-                self.tex_handle_pre = Some(ctx.load_texture("pre_image", image, Default::default())); 
+                param_changed |= true;
+            }
+
+            if let (true, RenderMode::Section, Some(opts)) = (is_flow, self.render_mode, &mut self.poincare) {
+                let mut section_changed = false;
+                ui.horizontal(|ui|{
+                    if let Some(period) = self.attractor.forcing_period() {
+                        let mut strobe = matches!(opts.section, Section::Stroboscopic { .. });
+                        if ui.checkbox(&mut strobe, "stroboscopic").changed() {
+                            let mut normal = vec![0.0; dim];
+                            normal[dim - 1] = 1.0;
+                            opts.section = if strobe {Section::Stroboscopic { period }} else {Section::Plane { normal, offset: 0.0 }};
+                            section_changed |= true;
+                        }
+                    }
+                    match &mut opts.section {
+                        Section::Plane { normal, offset } => {
+                            for (i, n) in normal.iter_mut().enumerate() {
+                                section_changed |= ui.add(
+                                    egui::DragValue::new(n).clamp_range(-1.0..=1.0).fixed_decimals(2).speed(0.01).prefix(format!("n{}: ", i))
+                                ).changed();
+                            }
+                            section_changed |= ui.add(
+                                egui::DragValue::new(offset).fixed_decimals(3).speed(0.01).prefix("offset: ")
+                            ).changed();
+                        },
+                        Section::Stroboscopic { period } => {
+                            ui.label(format!("period: {:.4}", period));
+                        },
+                    }
+                });
+                ui.horizontal(|ui|{
+                    for (axis, label) in opts.axes.iter_mut().zip(["horizontal: x", "vertical: x"]) {
+                        section_changed |= ui.add(
+                            egui::DragValue::new(axis).clamp_range(0..=dim - 1).prefix(label)
+                        ).changed();
+                    }
+                    section_changed |= ui.add(
+                        egui::DragValue::new(&mut opts.points).clamp_range(1000..=10000000).prefix("points: ")
+                    ).changed();
+                });
+                if section_changed {
+                    self.attractor.param_changed(true);
+                    param_changed |= true;
+                }
+            }
+            else if is_flow && self.render_mode == RenderMode::Section && self.poincare_task.is_some() {
+                ui.label("searching the section...");
+            }
+
+            if (param_changed || color_changed) && !self.open_window {
+                self.update_preview(ctx);
+            }
+            if let Some(task) = &self.preview_task {
+                if task_progress(ui, task) {
+                    task.cancel();
+                }
             }
             if let Some(handle) = &self.tex_handle_pre {
                 let image = egui::Image::from_texture(
                     egui::load::SizedTexture::new(handle.id(), handle.size_vec2())
-                );
-                ui.add(image);
+                ).sense(egui::Sense::drag());
+                let response = ui.add(image);
+                // drag the preview to rotate the camera
+                if response.dragged() && !self.open_window {
+                    if let Some(camera) = self.attractor.state_mut().camera_mut() {
+                        let delta = response.drag_delta();
+                        camera.rotate(delta.x as f64 * 0.01, delta.y as f64 * 0.01);
+                        self.update_preview(ctx);
+                    }
+                }
             }
         });
 
+        let (mut update_high, mut save_high) = (false, false);
         egui::Window::new("high_resolution_image").open(&mut self.open_window).show(ctx, |ui| {
             ui.label("high resolution image");
             ui.horizontal(|ui| {
-                if ui.add(egui::Button::new("Update")).clicked() {
-                    let start = time::Instant::now();
-                    let image = image2texture(
-                        self.attractor.gen_img(self.num_iter_high, 1024, 1024, &self.palette)
+                if let Some(job) = &self.job {
+                    ui.add(
+                        egui::ProgressBar::new(job.progress.fraction())
+                        .show_percentage()
+                        .desired_width(200.0)
                     );
-                    self.elapsed = start.elapsed();
-                    self.tex_handle_high = Some(ctx.load_texture("high_image", image, Default::default()));
-                    self.attractor.param_changed(false);
+                    if ui.add(egui::Button::new("Cancel")).clicked() {
+                        job.progress.cancel();
+                    }
+                    ui.label(format!("{:.3} sec", job.start.elapsed().as_secs_f32()));
+                    return;
                 }
+                update_high |= ui.add(egui::Button::new("Update")).clicked();
                 ui.label(format!("{:.3} sec", self.elapsed.as_secs_f32()));
                 
-                if ui.add(egui::Button::new("Save Image")).clicked() {
-                    let dialog = rfd::FileDialog::new()
-                        .set_file_name(self.attractor.name().replace(' ', "_"))
-                        .set_directory("/")
-                        .add_filter("PNG", &["png"])
-                        .save_file();
-                    if let Some(path) = dialog {
-                        let save_result = self.attractor.save_img(&path, self.num_iter_high, 1024, 1024, &self.palette);
-                        rfd::MessageDialog::new()
-                        .set_title("Message")
-                        .set_description(
-                            if save_result.is_ok() {format!("{:?} saved", path.file_name().unwrap())} else {"Failed to save".to_owned()}
-                        )
-                        .set_buttons(rfd::MessageButtons::Ok)
-                        .show();
-                    }
-                }
+                save_high |= ui.add(egui::Button::new("Save Image")).clicked();
             });
             
             if let Some(handle) = &self.tex_handle_high {
@@ -447,11 +1217,433 @@ impl eframe::App for MyApp {
                 ui.add(image);
             }
         });
-        
+        if update_high {
+            self.start_high_res(ctx);
+        }
+        if save_high {
+            let dialog = rfd::FileDialog::new()
+                .set_file_name(self.attractor.name().replace(' ', "_"))
+                .set_directory("/")
+                .add_filter("PNG", &["png"])
+                .save_file();
+            if let Some(path) = dialog {
+                let save_result = self.save_image(&path);
+                rfd::MessageDialog::new()
+                .set_title("Message")
+                .set_description(
+                    if save_result.is_ok() {format!("{:?} saved", path.file_name().unwrap())} else {"Failed to save".to_owned()}
+                )
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show();
+            }
+        }
+
+        // the options are reset when they do not fit the current system
+        let (coef_num, dim) = (self.attractor.coefs().len(), self.attractor.state().get_xs().len());
+        if !self.bifurcation.as_ref().is_some_and(|opts| opts.coef < coef_num && opts.component < dim) {
+            self.bifurcation = render::bifurcation::BifurcationOptions::new(self.attractor.as_ref(), 0);
+        }
+        egui::Window::new("bifurcation_diagram").open(&mut self.open_bifurcation).show(ctx, |ui| {
+            let Some(opts) = &mut self.bifurcation else {
+                ui.label("this system has no coefficients to sweep");
+                return;
+            };
+            ui.horizontal(|ui| {
+                let coef = opts.coef;
+                ui.add(egui::DragValue::new(&mut opts.coef).clamp_range(0..=coef_num.saturating_sub(1)).prefix("coef: a"));
+                if opts.coef != coef {
+                    opts.range = self.attractor.coef_ranges()[opts.coef].clone();
+                }
+                let (mut start, mut end) = (*opts.range.start(), *opts.range.end());
+                let speed = self.attractor.speeds()[opts.coef];
+                ui.add(egui::DragValue::new(&mut start).fixed_decimals(3).speed(speed).prefix("from: "));
+                ui.add(egui::DragValue::new(&mut end).fixed_decimals(3).speed(speed).prefix("to: "));
+                opts.range = start..=end;
+                ui.add(egui::DragValue::new(&mut opts.component).clamp_range(0..=dim - 1).prefix("component: x"));
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut opts.transient).clamp_range(0..=10000000).prefix("transient: "));
+                ui.add(egui::DragValue::new(&mut opts.samples).clamp_range(10..=100000).prefix("samples: "));
+                ui.add(egui::DragValue::new(&mut opts.stride).clamp_range(1..=100000).prefix("stride: "));
+            });
+            match &self.bifurcation_task {
+                Some(task) => {
+                    if task_progress(ui, task) {
+                        task.cancel();
+                    }
+                },
+                None => {
+                    if ui.add(egui::Button::new("Generate")).clicked() {
+                        let (w, h) = BIFURCATION_SIZE;
+                        let (attractor, opts) = (self.attractor.clone_box(), opts.clone());
+                        self.bifurcation_task = Some(Task::spawn(ctx, move |progress| {
+                            let hist = render::bifurcation::gen_bifurcation(attractor.as_ref(), &opts, w, h, progress)?;
+                            Some((hist, opts.samples))
+                        }));
+                    }
+                },
+            }
+            if let Some(handle) = &self.tex_handle_bif {
+                let image = egui::Image::from_texture(
+                    egui::load::SizedTexture::new(handle.id(), handle.size_vec2())
+                ).shrink_to_fit()
+                .maintain_aspect_ratio(true);
+                ui.add(image);
+            }
+        });
+
+        if let Some(result) = self.bifurcation_task.as_ref().and_then(|task| task.poll(ctx)) {
+            self.bifurcation_task = None;
+            if let Some((hist, samples)) = result {
+                let (w, h) = BIFURCATION_SIZE;
+                let image = image2texture(render::colorize(&hist, w * samples, w, h, &self.palette));
+                self.tex_handle_bif = Some(ctx.load_texture("bifurcation_image", image, Default::default()));
+            }
+        }
+
+        if self.plane.is_none() {
+            self.plane = analysis::PlaneOptions::new(self.attractor.as_ref());
+        }
+        let mut picked = None;
+        egui::Window::new("parameter_plane").open(&mut self.open_plane).show(ctx, |ui| {
+            let Some(opts) = &mut self.plane else {
+                ui.label("this system has no coefficients to sweep");
+                return;
+            };
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("plane_metric")
+                .selected_text(opts.metric.name())
+                .show_ui(ui, |ui| {
+                    for metric in analysis::PlaneMetric::ALL {
+                        ui.selectable_value(&mut opts.metric, metric, metric.name());
+                    }
+                });
+                ui.add(egui::DragValue::new(&mut opts.iters).clamp_range(100..=10000000).prefix("iter: "));
+                ui.add(egui::DragValue::new(&mut opts.skip).clamp_range(0..=10000000).prefix("skip: "));
+                if opts.metric == analysis::PlaneMetric::Period {
+                    ui.add(egui::DragValue::new(&mut opts.max_period).clamp_range(2..=1024).prefix("max period: "));
+                }
+            });
+            for (k, label) in ["horizontal", "vertical"].into_iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let coef = opts.coefs[k];
+                    ui.add(egui::DragValue::new(&mut opts.coefs[k]).clamp_range(0..=coef_num.saturating_sub(1)).prefix(format!("{}: a", label)));
+                    if opts.coefs[k] != coef {
+                        opts.ranges[k] = self.attractor.coef_ranges()[opts.coefs[k]].clone();
+                    }
+                    let (mut start, mut end) = (*opts.ranges[k].start(), *opts.ranges[k].end());
+                    let speed = self.attractor.speeds()[opts.coefs[k]];
+                    ui.add(egui::DragValue::new(&mut start).fixed_decimals(3).speed(speed).prefix("from: "));
+                    ui.add(egui::DragValue::new(&mut end).fixed_decimals(3).speed(speed).prefix("to: "));
+                    opts.ranges[k] = start..=end;
+                });
+            }
+            match &self.plane_task {
+                Some(task) => {
+                    if task_progress(ui, task) {
+                        task.cancel();
+                    }
+                },
+                None => {
+                    if ui.add(egui::Button::new("Generate")).clicked() {
+                        let (w, h) = PARAMETER_PLANE_SIZE;
+                        let (attractor, opts) = (self.attractor.clone_box(), opts.clone());
+                        self.plane_task = Some(Task::spawn(ctx, move |progress| {
+                            let values = analysis::gen_parameter_plane(attractor.as_ref(), &opts, w, h, progress)?;
+                            Some((values, opts))
+                        }));
+                    }
+                },
+            }
+            ui.label("click to load the coefficients");
+            if let (Some(handle), Some(shown)) = (&self.tex_handle_plane, &self.plane_shown) {
+                let image = egui::Image::from_texture(
+                    egui::load::SizedTexture::new(handle.id(), handle.size_vec2())
+                ).sense(egui::Sense::click());
+                let response = ui.add(image);
+                if let (true, Some(pos)) = (response.clicked(), response.interact_pointer_pos()) {
+                    let (w, h) = PARAMETER_PLANE_SIZE;
+                    let rel = (pos - response.rect.min) / response.rect.size();
+                    let x = ((rel.x * w as f32) as usize).min(w - 1);
+                    let y = ((rel.y * h as f32) as usize).min(h - 1);
+                    picked = Some((shown.coefs, shown.coefs_at(x, y, w, h)));
+                }
+            }
+        });
+        if let Some(result) = self.plane_task.as_ref().and_then(|task| task.poll(ctx)) {
+            self.plane_task = None;
+            if let Some((values, opts)) = result {
+                let (w, h) = PARAMETER_PLANE_SIZE;
+                let levels = analysis::parameter_plane::levels(&values, &opts);
+                let image = image2texture(render::basin::colorize_levels(&levels, w, h, &self.palette));
+                self.tex_handle_plane = Some(ctx.load_texture("parameter_plane_image", image, Default::default()));
+                self.plane_shown = Some(opts);
+            }
+        }
+        if let Some(([a, b], (ca, cb))) = picked {
+            self.attractor.coefs_mut()[a] = ca;
+            self.attractor.coefs_mut()[b] = cb;
+            self.coefs_changed(ctx);
+        }
+        if let Some(result) = self.lyapunov_task.as_ref().and_then(|task| task.poll(ctx)) {
+            self.lyapunov_task = None;
+            self.lyapunov = result;
+        }
+        if let Some(result) = self.dimension_task.as_ref().and_then(|task| task.poll(ctx)) {
+            self.dimension_task = None;
+            self.dimension = result;
+        }
+        if let Some(result) = self.search_task.as_ref().and_then(|task| task.poll(ctx)) {
+            self.search_task = None;
+            self.search_status = match result {
+                // the system may have been switched during the search
+                Some((name, Some(c))) if name == self.attractor.name() && c.coefs.len() == self.attractor.coefs().len() => {
+                    self.attractor.coefs_mut().copy_from_slice(&c.coefs);
+                    self.coefs_changed(ctx);
+                    format!("found in {} tries (λ1: {:.3}, D2: {:.2})", c.tries, c.lyapunov, c.dimension)
+                },
+                Some((_, Some(_))) => "".to_string(),
+                Some((_, None)) => format!("nothing found in {} tries", analysis::SearchOptions::default().max_tries),
+                None => "cancelled".to_string(),
+            };
+        }
+
+        let (mut restore, mut remove) = (None, None);
+        egui::Window::new("favorites").open(&mut self.open_favorites).show(ctx, |ui| {
+            if self.favorites.is_empty() {
+                ui.label("Nothing yet, add the current attractor with ★ Favorite");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("favorites_grid").show(ui, |ui| {
+                    for (i, favorite) in self.favorites.iter_mut().enumerate() {
+                        if favorite.texture.is_none() {
+                            favorite.texture = favorite.thumbnail.as_ref()
+                                .map(|img| ctx.load_texture("favorite_thumbnail", image2texture(img.clone()), Default::default()));
+                        }
+                        ui.vertical(|ui| {
+                            let clicked = match &favorite.texture {
+                                Some(handle) => ui.add(egui::ImageButton::new(
+                                    egui::load::SizedTexture::new(handle.id(), handle.size_vec2())
+                                )).clicked(),
+                                None => ui.add_sized([64.0, 64.0], egui::Button::new("no preview")).clicked(),
+                            };
+                            if clicked {
+                                restore = Some(i);
+                            }
+                            ui.horizontal(|ui| {
+                                ui.label(favorite.attractor.name());
+                                if ui.small_button("✖").on_hover_text("remove").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                        });
+                        if (i + 1) % FAVORITES_COLUMNS == 0 {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+        });
+        if let Some(favorite) = restore.and_then(|i| self.favorites.get(i)) {
+            let (attractor, palette) = (favorite.attractor.clone_box(), favorite.palette.clone());
+            self.restore(ctx, attractor, palette);
+        }
+        if let Some(i) = remove {
+            self.favorites.remove(i);
+        }
+
+        let (mut show, mut remove, mut render_to) = (None, None, None);
+        let mut cancel = false;
+        egui::Window::new("animation").open(&mut self.open_animation).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("Add keyframe")).clicked() {
+                    // keyframes of another system cannot be interpolated with the current one
+                    let (n, dim) = (self.attractor.coefs().len(), self.attractor.state().get_init_val().len());
+                    self.keyframes.retain(|k| k.coefs.len() == n && k.init.len() == dim);
+                    self.keyframes.push(animation::Keyframe::new(1.0, self.attractor.as_ref(), &self.palette));
+                    animation::space_evenly(&mut self.keyframes);
+                }
+                if ui.add(egui::Button::new("Space evenly")).clicked() {
+                    animation::space_evenly(&mut self.keyframes);
+                }
+            });
+            if self.keyframes.is_empty() {
+                ui.label("Add the current parameters as keyframes, at least two");
+            }
+            for (i, key) in self.keyframes.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut key.time)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.01)
+                        .prefix("time: ")
+                    );
+                    if ui.small_button("Show").clicked() {
+                        show = Some(i);
+                    }
+                    if ui.small_button("✖").on_hover_text("remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.animation.frames)
+                    .clamp_range(1..=10000)
+                    .prefix("frames: ")
+                );
+                ui.add(egui::DragValue::new(&mut self.animation.fps)
+                    .clamp_range(1..=120)
+                    .prefix("fps: ")
+                );
+                let mut smooth = self.animation.easing == animation::Easing::Smooth;
+                if ui.checkbox(&mut smooth, "ease in/out").changed() {
+                    self.animation.easing = if smooth {animation::Easing::Smooth} else {animation::Easing::Linear};
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.animation.num_iter)
+                    .clamp_range(1000..=100000000)
+                    .speed(1000)
+                    .prefix("iter: ")
+                );
+                ui.add(egui::DragValue::new(&mut self.animation.width)
+                    .clamp_range(16..=MAX_SIZE)
+                    .prefix("w: ")
+                );
+                ui.add(egui::DragValue::new(&mut self.animation.height)
+                    .clamp_range(16..=MAX_SIZE)
+                    .prefix("h: ")
+                );
+            });
+            egui::ComboBox::from_label("format")
+                .selected_text(self.animation.format.name())
+                .show_ui(ui, |ui| {
+                    for format in animation::AnimationFormat::ALL {
+                        ui.selectable_value(&mut self.animation.format, format, format.name());
+                    }
+                });
+            match &self.animation_job {
+                Some(job) => {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::ProgressBar::new(job.progress.fraction()).show_percentage());
+                        if ui.add(egui::Button::new("Cancel")).clicked() {
+                            cancel = true;
+                        }
+                    });
+                },
+                None => {
+                    if ui.add_enabled(self.keyframes.len() >= 2, egui::Button::new("Render…")).clicked() {
+                        let format = self.animation.format;
+                        render_to = rfd::FileDialog::new()
+                            .set_file_name(self.attractor.name().replace(' ', "_"))
+                            .set_directory("/")
+                            .add_filter(format.name(), &[format.extension()])
+                            .save_file();
+                    }
+                },
+            }
+            if !self.animation_status.is_empty() {
+                ui.label(&self.animation_status);
+            }
+        });
+        if let Some(key) = show.and_then(|i| self.keyframes.get(i)) {
+            let mut attractor = self.attractor.clone_box();
+            if attractor.coefs().len() == key.coefs.len() && attractor.state().get_init_val().len() == key.init.len() {
+                attractor.coefs_mut().copy_from_slice(&key.coefs);
+                attractor.state_mut().get_init_val_mut().copy_from_slice(&key.init);
+                let palette = key.palette.clone();
+                self.restore(ctx, attractor, palette);
+            }
+        }
+        if let Some(i) = remove {
+            self.keyframes.remove(i);
+        }
+        if cancel {
+            if let Some(job) = self.animation_job.take() {
+                job.progress.cancel();
+                self.animation_status = "Cancelled".to_string();
+            }
+        }
+        if let Some(mut path) = render_to {
+            if animation::AnimationFormat::from_path(&path) != Some(self.animation.format) {
+                path.set_extension(self.animation.format.extension());
+            }
+            self.start_animation(ctx, path);
+        }
+
+        // a snapshot is taken once an edit is done, values being dragged or typed are still changing
+        let editing = ctx.input(|i| i.pointer.any_down()) || ctx.memory(|m| m.focused().is_some());
+        if !editing {
+            let thumbnail = &self.thumbnail;
+            self.history.record(self.attractor.as_ref(), &self.palette, |key| {
+                let (_, image) = thumbnail.as_ref().filter(|(k, _)| k == key)?;
+                Some(ctx.load_texture("history_thumbnail", image2texture(image.clone()), Default::default()))
+            });
+            let (undo, redo) = ctx.input_mut(|i| {
+                // the more specific shortcut first
+                let redo = i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z));
+                (i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z)), redo)
+            });
+            if undo && self.history.can_undo() {
+                jump = Some(self.history.current() - 1);
+            }
+            if redo && self.history.can_redo() {
+                jump = Some(self.history.current() + 1);
+            }
+        }
+        if let Some(snapshot) = jump.and_then(|i| self.history.jump(i)) {
+            let (attractor, palette) = (snapshot.attractor.clone_box(), snapshot.palette.clone());
+            self.restore(ctx, attractor, palette);
+        }
+        if !self.open_window {
+            self.cancel_high_res();
+        }
+    }
+}
+
+// the points of the curve with the scaling region highlighted and the fitted line through it
+fn log_log_plot(ui: &mut egui::Ui, scaling: &analysis::Scaling, x_label: &str, y_label: &str) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(200.0, 140.0), egui::Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals();
+    painter.rect_stroke(rect, 0.0, visuals.widgets.noninteractive.bg_stroke);
+    let pts = scaling.log_scales.iter().cloned().zip(scaling.log_measures.iter().cloned())
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .collect::<Vec<_>>();
+    if pts.len() < 2 {return;}
+    let (x0, x1, y0, y1) = pts.iter().fold((f64::MAX, f64::MIN, f64::MAX, f64::MIN), |(x0, x1, y0, y1), &(x, y)| {
+        (x0.min(x), x1.max(x), y0.min(y), y1.max(y))
+    });
+    let inner = rect.shrink(8.0);
+    let to_screen = |x: f64, y: f64| egui::pos2(
+        inner.left() + inner.width() * ((x - x0) / (x1 - x0).max(1e-12)) as f32,
+        inner.bottom() - inner.height() * ((y - y0) / (y1 - y0).max(1e-12)) as f32,
+    );
+    let fit = scaling.fit.clone();
+    for (i, (x, y)) in scaling.log_scales.iter().zip(scaling.log_measures.iter()).enumerate() {
+        if !(x.is_finite() && y.is_finite()) {continue;}
+        let color = if fit.contains(&i) {egui::Color32::LIGHT_BLUE} else {visuals.weak_text_color()};
+        painter.circle_filled(to_screen(*x, *y), 2.0, color);
+    }
+    if fit.len() >= 2 && scaling.dimension.is_finite() {
+        let n = fit.len() as f64;
+        let (mx, my) = fit.clone().fold((0.0, 0.0), |(a, b), i| (a + scaling.log_scales[i] / n, b + scaling.log_measures[i] / n));
+        let (fx0, fx1) = (scaling.log_scales[fit.start], scaling.log_scales[fit.end - 1]);
+        let line = |x: f64| to_screen(x, my + scaling.dimension * (x - mx));
+        painter.line_segment([line(fx0), line(fx1)], egui::Stroke::new(1.0, egui::Color32::YELLOW));
     }
+    let font = egui::FontId::proportional(10.0);
+    painter.text(rect.left_top() + egui::vec2(4.0, 2.0), egui::Align2::LEFT_TOP, y_label, font.clone(), visuals.text_color());
+    painter.text(rect.right_bottom() - egui::vec2(4.0, 2.0), egui::Align2::RIGHT_BOTTOM, x_label, font, visuals.text_color());
 }
 
 fn image2texture(img: DynamicImage) -> egui::ColorImage {
+    let img = if img.width().max(img.height()) > MAX_TEXTURE_SIZE {
+        img.thumbnail(MAX_TEXTURE_SIZE, MAX_TEXTURE_SIZE)
+    }
+    else {
+        img
+    };
     match &img {
         DynamicImage::ImageRgb8(image) => {
             egui::ColorImage::from_rgb(
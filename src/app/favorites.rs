@@ -0,0 +1,68 @@
+use image::{DynamicImage, ImageFormat};
+use serde::{Serialize, Deserialize};
+use std::io::Cursor;
+
+use crate::attractors::Attractor;
+use crate::params;
+use crate::util::Palette;
+
+// key of the favorites in the eframe storage
+const STORAGE_KEY: &str = "favorites";
+
+pub struct Favorite {
+    pub attractor: Box<dyn Attractor>,
+    pub palette: Palette,
+    pub thumbnail: Option<DynamicImage>,
+    // uploaded when the gallery is first shown
+    pub texture: Option<egui::TextureHandle>,
+}
+
+// a favorite in the storage, the parameter document and the thumbnail as a hex encoded PNG
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    document: String,
+    thumbnail: String,
+}
+
+impl Favorite {
+    pub fn new(at: &dyn Attractor, plt: &Palette, thumbnail: Option<DynamicImage>) -> Self {
+        Self {
+            attractor: at.clone_box(),
+            palette: plt.clone(),
+            thumbnail,
+            texture: None,
+        }
+    }
+    fn to_stored(&self) -> Option<Stored> {
+        let document = params::document_to_string(self.attractor.as_ref(), Some(&self.palette), None).ok()?;
+        let mut png = vec![];
+        if let Some(thumbnail) = &self.thumbnail {
+            thumbnail.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+        }
+        let thumbnail = png.iter().map(|b| format!("{:02x}", b)).collect();
+        Some(Stored { document, thumbnail })
+    }
+    fn from_stored(stored: &Stored) -> Option<Self> {
+        let document = params::document_from_value(serde_json::from_str(&stored.document).ok()?).ok()?;
+        let png = (0..stored.thumbnail.len() / 2)
+            .map(|i| u8::from_str_radix(stored.thumbnail.get(2 * i..2 * i + 2)?, 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            attractor: document.attractor,
+            palette: document.palette.unwrap_or_default(),
+            thumbnail: image::load_from_memory_with_format(&png, ImageFormat::Png).ok(),
+            texture: None,
+        })
+    }
+}
+
+// favorites that cannot be read, e.g. of systems of a newer dysy, are skipped
+pub fn load(storage: &dyn eframe::Storage) -> Vec<Favorite> {
+    let stored: Vec<Stored> = eframe::get_value(storage, STORAGE_KEY).unwrap_or_default();
+    stored.iter().filter_map(Favorite::from_stored).collect()
+}
+
+pub fn save(storage: &mut dyn eframe::Storage, favorites: &[Favorite]) {
+    let stored = favorites.iter().filter_map(Favorite::to_stored).collect::<Vec<_>>();
+    eframe::set_value(storage, STORAGE_KEY, &stored);
+}
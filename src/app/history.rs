@@ -0,0 +1,102 @@
+use crate::attractors::Attractor;
+use crate::camera::Camera;
+use crate::integrator::Integrator;
+use crate::util::Palette;
+
+// snapshots kept, the oldest are dropped first
+const LIMIT: usize = 100;
+
+// what the side panels edit: the system, coefficients, initial values, step, camera, integrator
+// and palette. the running state of the orbit is left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    name: String,
+    map_str: String,
+    coefs: Vec<f64>,
+    init: Vec<f64>,
+    dt: Option<f64>,
+    camera: Option<Camera>,
+    integrator: Option<Integrator>,
+    palette: Palette,
+}
+
+impl Key {
+    pub fn new(at: &dyn Attractor, plt: &Palette) -> Self {
+        let state = at.state();
+        Self {
+            name: at.name().into(),
+            map_str: at.map_str().into(),
+            coefs: at.coefs().to_vec(),
+            init: state.get_init_val().to_vec(),
+            dt: state.get_dt(),
+            camera: state.camera().copied(),
+            integrator: state.get_dt().map(|_| state.integrator()),
+            palette: plt.clone(),
+        }
+    }
+    // compared in place, without copying the parameters, as it is checked every frame
+    pub fn matches(&self, at: &dyn Attractor, plt: &Palette) -> bool {
+        let state = at.state();
+        self.name == at.name()
+            && self.map_str == at.map_str()
+            && self.coefs == at.coefs()
+            && self.init == state.get_init_val()
+            && self.dt == state.get_dt()
+            && self.camera == state.camera().copied()
+            && self.integrator == state.get_dt().map(|_| state.integrator())
+            && self.palette == *plt
+    }
+}
+
+pub struct Snapshot {
+    pub attractor: Box<dyn Attractor>,
+    pub palette: Palette,
+    pub thumbnail: Option<egui::TextureHandle>,
+    key: Key,
+}
+
+// undo stack of snapshots, `current` is the one shown
+#[derive(Default)]
+pub struct History {
+    entries: Vec<Snapshot>,
+    current: usize,
+}
+
+impl History {
+    // records the state if it differs from the current snapshot, the undone snapshots are dropped
+    pub fn record(
+        &mut self, at: &dyn Attractor, plt: &Palette, thumbnail: impl FnOnce(&Key) -> Option<egui::TextureHandle>
+    ) -> bool {
+        if self.entries.get(self.current).is_some_and(|s| s.key.matches(at, plt)) {return false;}
+        let key = Key::new(at, plt);
+        self.entries.truncate(self.current + 1);
+        if self.entries.len() == LIMIT {
+            self.entries.remove(0);
+        }
+        self.entries.push(Snapshot {
+            attractor: at.clone_box(),
+            palette: plt.clone(),
+            thumbnail: thumbnail(&key),
+            key,
+        });
+        self.current = self.entries.len() - 1;
+        true
+    }
+    pub fn jump(&mut self, i: usize) -> Option<&Snapshot> {
+        if i >= self.entries.len() {return None;}
+        self.current = i;
+        self.entries.get(i)
+    }
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+    pub fn can_redo(&self) -> bool {
+        self.current + 1 < self.entries.len()
+    }
+    pub fn entries(&self) -> &[Snapshot] {
+        &self.entries
+    }
+    pub fn current(&self) -> usize {
+        self.current
+    }
+}
@@ -13,6 +13,9 @@ pub use duffing::Duffing;
 pub mod lorentz;
 pub use lorentz::Lorenz;
 
+pub mod chua;
+pub use chua::{Chua, ChuaDiode};
+
 pub mod quadratic;
 pub use quadratic::Quadratic;
 
@@ -22,8 +25,56 @@ pub use symmetric::Symmetric;
 pub mod polar;
 pub use polar::Polar;
 
+pub mod henon;
+pub use henon::Henon;
+
+pub mod ikeda;
+pub use ikeda::Ikeda;
+
+pub mod tinkerbell;
+pub use tinkerbell::Tinkerbell;
+
+pub mod de_jong;
+pub use de_jong::DeJong;
+
+pub mod gumowski_mira;
+pub use gumowski_mira::GumowskiMira;
+
+pub mod hopalong;
+pub use hopalong::Hopalong;
+
+pub mod bedhead;
+pub use bedhead::Bedhead;
+
 pub mod double_pendulum;
 pub use double_pendulum::DoublePendulum;
 
+pub mod rossler;
+pub use rossler::Rossler;
+
+pub mod chen;
+pub use chen::Chen;
+
+pub mod lu;
+pub use lu::Lu;
+
+pub mod aizawa;
+pub use aizawa::Aizawa;
+
+pub mod thomas;
+pub use thomas::Thomas;
+
+pub mod halvorsen;
+pub use halvorsen::Halvorsen;
+
+pub mod dadras;
+pub use dadras::Dadras;
+
+pub mod rabinovich_fabrikant;
+pub use rabinovich_fabrikant::RabinovichFabrikant;
+
+pub mod sprott;
+pub use sprott::{Sprott, SprottCase};
+
 pub mod custom;
 pub use custom::Custom;
\ No newline at end of file
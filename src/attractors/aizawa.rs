@@ -0,0 +1,61 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aizawa {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Aizawa {
+    fn default() -> Self {
+        let range = vec![
+                (0.5..=1.2),(0.4..=1.0),(0.3..=0.9),(2.0..=5.0),(0.0..=0.5),(0.0..=0.3)
+            ];
+        Self {
+            name: "Aizawa Attractor".into(),
+            map_str: "dx/dt = (z - a1) * x - a3 * y, dy/dt = a3 * x + (z - a1) * y, dz/dt = a2 + a0 * z - z^3 / 3 - (x^2 + y^2) * (1 + a4 * z) + a5 * z * x^3".into(),
+            range,
+            speeds: vec![0.001; 6],
+            coefs: vec![0.95, 0.7, 0.6, 3.5, 0.25, 0.1],
+            state: State::new(3, -1.0..=1.0, Some(0.005)).with_init(&[0.1, 0.0, 0.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Aizawa {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        let (x, _, z) = self.state.get_xyz();
+        (x, z)
+    }
+}
+
+impl Flow for Aizawa {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        let (a, b, c, d, e, f) = (self.coefs[0], self.coefs[1], self.coefs[2], self.coefs[3], self.coefs[4], self.coefs[5]);
+        dx[0] = (x[2] - b) * x[0] - d * x[1];
+        dx[1] = d * x[0] + (x[2] - b) * x[1];
+        dx[2] = c + a * x[2] - x[2].powi(3) / 3.0 - (x[0] * x[0] + x[1] * x[1]) * (1.0 + e * x[2]) + f * x[2] * x[0].powi(3);
+    }
+}
\ No newline at end of file
@@ -1,15 +1,96 @@
-use std::path::PathBuf;
-use image::{DynamicImage, error::ImageResult};
+use std::path::Path;
+use rand::{thread_rng, Rng};
+use image::{DynamicImage, ImageFormat, ImageError, error::{ImageResult, EncodingError}};
 use crate::util::Palette;
 use crate::state::State;
+use crate::render;
+use crate::params;
+
+// cached histogram of the last generated image. clones start empty, seeds and snapshots of an
+// attractor only need its parameters and state
+#[derive(Debug, Default)]
+pub struct ImageCache(Vec<f64>);
+
+impl Clone for ImageCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::ops::Deref for ImageCache {
+    type Target = Vec<f64>;
+    fn deref(&self) -> &Vec<f64> {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for ImageCache {
+    fn deref_mut(&mut self) -> &mut Vec<f64> {
+        &mut self.0
+    }
+}
 
 // dynamical system trajectory generator analyzer
-pub trait Attractor: erased_serde::Serialize {
+pub trait Attractor: erased_serde::Serialize + Send {
     fn apply_map_func(&mut self);
+    // 2D point of the current state drawn into the image
+    fn project(&self) -> (f64, f64) {
+        self.state().get_xy()
+    }
+    // point drawn into the image, through the camera of the state if it has one
+    fn view(&self) -> (f64, f64) {
+        match self.state().camera() {
+            Some(camera) => {
+                let (x, y, z) = self.state().get_xyz();
+                camera.project(x, y, z)
+            },
+            None => self.project(),
+        }
+    }
+    // warm-up iterations excluded from the image
+    fn skip(&self) -> usize {
+        if self.state().get_dt().is_some() {0} else {500}
+    }
+    // iterations used to search the bounding box of the image
+    fn search_iters(&self, n: usize) -> usize {
+        if self.state().get_dt().is_some() {100000.max(n/10)} else {50000}
+    }
+    // orbits from nearby seeds cover the same attractor, so the iterations can be split across threads
+    fn ergodic(&self) -> bool {
+        self.state().get_dt().is_none()
+    }
+    // period of an external forcing, sampled by stroboscopic Poincare sections
+    fn forcing_period(&self) -> Option<f64> {
+        None
+    }
+    // components of the state which are angles
+    fn angles(&self) -> Vec<usize> {
+        vec![]
+    }
+    // separable Hamiltonian flow, positions then velocities with accelerations independent of the
+    // velocities, so the symplectic leapfrog applies
+    fn separable(&self) -> bool {
+        false
+    }
+    // called before the trajectory is generated, false if the system cannot be iterated
+    fn prepare(&mut self) -> bool {
+        true
+    }
     // trajectory image
-    fn gen_img(&mut self, n: usize, w: usize, h: usize, plt: &Palette) -> DynamicImage;
-    fn save_img(&mut self, path: &PathBuf, n: usize, w: usize, h: usize, plt: &Palette) -> ImageResult<()> {
+    fn gen_img(&mut self, n: usize, w: usize, h: usize, plt: &Palette) -> DynamicImage {
+        if self.is_param_changed() || self.img_vec_mut().len() != w * h {
+            let img_vec = render::gen_hist(self, n, w, h);
+            *self.img_vec_mut() = img_vec;
+        }
+        render::colorize(self.img_vec_mut(), n, w, h, plt)
+    }
+    // PNGs carry the parameters, palette and iteration count, see params::save_png
+    fn save_img(&mut self, path: &Path, n: usize, w: usize, h: usize, plt: &Palette) -> ImageResult<()> {
         let img = self.gen_img(n, w, h, plt);
+        if params::is_png(path) {
+            return params::save_png(&img, path, self, plt, Some(n))
+                .map_err(|e| ImageError::Encoding(EncodingError::new(ImageFormat::Png.into(), e)));
+        }
         img.save(path)
     }
     fn name(&self) -> &str;
@@ -17,11 +98,72 @@ pub trait Attractor: erased_serde::Serialize {
     fn speeds(&self) -> Vec<f64>;
     fn coefs(&self) -> &[f64];
     fn coefs_mut(&mut self) -> &mut [f64];
-    fn change_random_coefs(&mut self);
+    // coefficients drawn uniformly from their ranges
+    fn change_random_coefs(&mut self) {
+        let mut rng = thread_rng();
+        let ranges = self.coef_ranges();
+        for (coef, range) in self.coefs_mut().iter_mut().zip(ranges) {
+            *coef = rng.gen_range(range);
+        }
+    }
     fn coef_ranges(&self) -> Vec<std::ops::RangeInclusive<f64>>;
     fn state(&self) -> &State;
     fn state_mut(&mut self) -> &mut State;
     fn param_changed(&mut self, flag: bool);
+    fn is_param_changed(&self) -> bool;
+    // cached normalized histogram of the last generated image
+    fn img_vec_mut(&mut self) -> &mut Vec<f64>;
+    fn clone_box(&self) -> Box<dyn Attractor>;
+}
+
+impl Clone for Box<dyn Attractor> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
 }
 
-erased_serde::serialize_trait_object!(Attractor);
\ No newline at end of file
+erased_serde::serialize_trait_object!(Attractor);
+
+// accessors of the fields every system has: name, map_str, range, speeds, coefs, state, img_vec and
+// param_changed. expands inside `impl Attractor for ...`, which then only adds the map and overrides
+macro_rules! attractor_fields {
+    () => {
+        fn name(&self) -> &str {
+            &self.name
+        }
+        fn map_str(&self) -> &str {
+            &self.map_str
+        }
+        fn coef_ranges(&self) -> Vec<std::ops::RangeInclusive<f64>> {
+            self.range.clone()
+        }
+        fn speeds(&self) -> Vec<f64> {
+            self.speeds.clone()
+        }
+        fn coefs(&self) -> &[f64] {
+            &self.coefs
+        }
+        fn coefs_mut(&mut self) -> &mut [f64] {
+            &mut self.coefs
+        }
+        fn state(&self) -> &$crate::state::State {
+            &self.state
+        }
+        fn state_mut(&mut self) -> &mut $crate::state::State {
+            &mut self.state
+        }
+        fn param_changed(&mut self, flag: bool) {
+            self.param_changed = flag;
+        }
+        fn is_param_changed(&self) -> bool {
+            self.param_changed
+        }
+        fn img_vec_mut(&mut self) -> &mut Vec<f64> {
+            &mut self.img_vec
+        }
+        fn clone_box(&self) -> Box<dyn $crate::attractors::Attractor> {
+            Box::new(self.clone())
+        }
+    };
+}
+pub(crate) use attractor_fields;
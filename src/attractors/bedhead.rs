@@ -0,0 +1,47 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bedhead {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Bedhead {
+    fn default() -> Self {
+        // a1 divides, so it is kept away from 0
+        let range = vec![
+                (-1.0..=1.0),(-1.0..=-0.2)
+            ];
+        Self {
+            name: "Bedhead Attractor".into(),
+            map_str: "x = sin(x * y / a1) * y + cos(a0 * x - y), y = x + sin(y) / a1".into(),
+            range,
+            speeds: vec![0.001; 2],
+            coefs: vec![-0.81, -0.92],
+            state: State::new(2, -1.0..=1.0, None).with_init(&[1.0, 1.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for Bedhead {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        self.state.set_xy(
+            (x * y / self.coefs[1]).sin() * y + (self.coefs[0] * x - y).cos(),
+            x + y.sin() / self.coefs[1]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chen {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Chen {
+    fn default() -> Self {
+        let range = vec![
+                (30.0..=40.0),(1.0..=5.0),(20.0..=30.0)
+            ];
+        Self {
+            name: "Chen Attractor".into(),
+            map_str: "dx/dt = a0 * (y - x), dy/dt = (a2 - a0) * x - x * z + a2 * y, dz/dt = x * y - a1 * z".into(),
+            range,
+            speeds: vec![0.01; 3],
+            coefs: vec![35.0, 3.0, 28.0],
+            state: State::new(3, -10.0..=10.0, Some(0.001)).with_init(&[-10.0, 0.0, 37.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Chen {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        let (x, _, z) = self.state.get_xyz();
+        (x, z)
+    }
+}
+
+impl Flow for Chen {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        dx[0] = self.coefs[0] * (x[1] - x[0]);
+        dx[1] = (self.coefs[2] - self.coefs[0]) * x[0] - x[0] * x[2] + self.coefs[2] * x[1];
+        dx[2] = x[0] * x[1] - self.coefs[1] * x[2];
+    }
+}
\ No newline at end of file
@@ -0,0 +1,110 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+// characteristic f(x) of the nonlinear resistor (Chua's diode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChuaDiode {
+    // slope a2 inside |x| < 1 and a3 outside
+    PiecewiseLinear,
+    // smooth a2 * x^3 + a3 * x
+    Cubic,
+}
+
+impl ChuaDiode {
+    pub const ALL: [ChuaDiode; 2] = [ChuaDiode::PiecewiseLinear, ChuaDiode::Cubic];
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChuaDiode::PiecewiseLinear => "Chua Attractor",
+            ChuaDiode::Cubic => "Chua Attractor (cubic)",
+        }
+    }
+    pub fn map_str(&self) -> &'static str {
+        match self {
+            ChuaDiode::PiecewiseLinear => "dx/dt = a0 * (y - x - f(x)), dy/dt = x - y + z, dz/dt = -a1 * y, f(x) = a3 * x + (a2 - a3) * (|x + 1| - |x - 1|) / 2",
+            ChuaDiode::Cubic => "dx/dt = a0 * (y - x - f(x)), dy/dt = x - y + z, dz/dt = -a1 * y, f(x) = a2 * x^3 + a3 * x",
+        }
+    }
+    // double scroll of both diodes, alpha and beta are the capacitor ratio C2 / C1 and the inductor
+    // ratio C2 * R^2 / L
+    fn coefs(&self) -> (Vec<f64>, Vec<std::ops::RangeInclusive<f64>>) {
+        match self {
+            ChuaDiode::PiecewiseLinear => (
+                vec![15.6, 28.0, -8.0 / 7.0, -5.0 / 7.0],
+                vec![(8.0..=16.0),(20.0..=32.0),(-1.4..=-1.0),(-0.8..=-0.5)],
+            ),
+            ChuaDiode::Cubic => (
+                vec![10.0, 16.0, 1.0 / 16.0, -7.0 / 6.0],
+                vec![(8.0..=12.0),(12.0..=20.0),(0.03..=0.1),(-1.3..=-1.1)],
+            ),
+        }
+    }
+    pub fn f(&self, coefs: &[f64], x: f64) -> f64 {
+        match self {
+            ChuaDiode::PiecewiseLinear => coefs[3] * x + 0.5 * (coefs[2] - coefs[3]) * ((x + 1.0).abs() - (x - 1.0).abs()),
+            ChuaDiode::Cubic => coefs[2] * x * x * x + coefs[3] * x,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chua {
+    pub name: String,
+    pub map_str: String,
+    pub diode: ChuaDiode,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Chua {
+    fn default() -> Self {
+        Self::with_diode(ChuaDiode::PiecewiseLinear)
+    }
+}
+impl Chua {
+    pub fn with_diode(diode: ChuaDiode) -> Self {
+        let (coefs, range) = diode.coefs();
+        Self {
+            name: diode.name().into(),
+            map_str: diode.map_str().into(),
+            diode,
+            range,
+            speeds: vec![0.01, 0.01, 0.001, 0.001],
+            coefs,
+            state: State::new(3, -1.0..=1.0, Some(0.002)).with_init(&[0.7, 0.0, 0.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Chua {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        let (x, _, z) = self.state.get_xyz();
+        (x, z)
+    }
+}
+
+impl Flow for Chua {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        dx[0] = self.coefs[0] * (x[1] - x[0] - self.diode.f(&self.coefs, x[0]));
+        dx[1] = x[0] - x[1] + x[2];
+        dx[2] = -self.coefs[1] * x[1];
+    }
+}
@@ -1,11 +1,10 @@
 use rand::{thread_rng, Rng};
-use image::{RgbImage, Rgb, DynamicImage};
-use serde::{Serialize};
+use serde::{Serialize, Deserialize};
 use serde::ser::{Serializer, SerializeStruct};
+use serde::de::{Deserializer, Error};
 use lieval::{Expr, EvalError};
 
-use super::attractor::Attractor;
-use crate::util::Palette;
+use super::attractor::{Attractor, ImageCache, attractor_fields};
 use crate::state::State;
 
 #[derive(Debug, Clone)]
@@ -18,7 +17,7 @@ pub struct Custom {
     pub state: State,
     pub expr: Expr,
     pub inner_expr: Expr,
-    pub img_vec: Vec<f64>,
+    pub img_vec: ImageCache,
     pub param_changed: bool,
 }
 
@@ -27,18 +26,48 @@ impl Serialize for Custom {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("Custom", 3)?;
+        let mut s = serializer.serialize_struct("Custom", 6)?;
         s.serialize_field("name", &self.name)?;
         s.serialize_field("map_str", &self.map_str)?;
         s.serialize_field("range", &self.range)?;
-        s.serialize_field("speeds", &self.name)?;
+        s.serialize_field("speeds", &self.speeds)?;
         s.serialize_field("coefs", &self.coefs)?;
         s.serialize_field("state", &self.state)?;
-        s.serialize_field("expr", &self.map_str)?;
         s.end()
     }
 }
 
+// the saved fields, the expressions are compiled again from map_str
+#[derive(Deserialize)]
+struct CustomFields {
+    map_str: String,
+    range: Vec<std::ops::RangeInclusive<f64>>,
+    speeds: Vec<f64>,
+    coefs: Vec<f64>,
+    state: State,
+}
+
+impl<'de> Deserialize<'de> for Custom {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let fields = CustomFields::deserialize(deserializer)?;
+        let custom = Custom::new(&fields.map_str)
+            .map_err(|e| D::Error::custom(format!("Invalid map_str: {:?}", e)))?;
+        if fields.coefs.len() != custom.coefs.len() {
+            return Err(D::Error::custom(format!("{} takes {} coefficients", fields.map_str, custom.coefs.len())));
+        }
+        Ok(Self {
+            range: fields.range,
+            speeds: fields.speeds,
+            coefs: fields.coefs,
+            state: fields.state,
+            ..custom
+        })
+    }
+}
+
 impl Default for Custom {
     fn default() -> Self {
         let range = vec![
@@ -55,12 +84,11 @@ impl Default for Custom {
             state: State::new(2, -2.0..=2.0, None),
             expr: Expr::new("0").unwrap(),
             inner_expr: Expr::new(map_str).unwrap(),
-            img_vec: vec![],
+            img_vec: ImageCache::default(),
             param_changed: true,
         }
     }
 }
-#[allow(dead_code)]
 impl Custom {
     pub fn new(map_str: &str) -> Result<Self, EvalError> {
         let mut rng = thread_rng();
@@ -86,114 +114,25 @@ impl Custom {
             state: State::new(2, -2.0..=2.0, None),
             expr: Expr::new("0").unwrap(),
             inner_expr: expr,
-            img_vec: vec![],
+            img_vec: ImageCache::default(),
             param_changed: true,
         })
     }
-
-    fn search_edges(&mut self, n: usize, skip: usize) -> (f64, f64, f64, f64) {
-        self.state.set_init();
-        let (mut top, mut left, mut bottom, mut right) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
-        for i in 0..n {
-            self.apply_map_func();
-            if i < skip {continue;}
-            let (x, y) = self.state.get_xy();
-            top = top.min(y);
-            left = left.min(x);
-            bottom = bottom.max(y);
-            right = right.max(x);
-        }
-        self.state.set_init();
-        (top, left, bottom, right)
-    }
-
-    fn gen_hist(&mut self, n: usize, w: usize, h: usize) {
-        self.expr = self.inner_expr.clone();
-        let vars = self.expr.vars();
-        for (v, c) in vars.iter().filter(|&v| v != "x" && v != "y").zip(self.coefs.iter()) {
-            self.expr.set_var(v, *c);
-        }
-        if self.expr.partial_eval().is_err() {return;};
-
-        let skip = 500;
-        let (top, left, bottom, right) = self.search_edges(50000, skip);
-
-        let wc = (right + left) * 0.5;
-        let hc = (bottom + top) * 0.5;
-        let m = (w as f64 / (right - left)).min(h as f64 / (bottom - top));
-
-        let mut hist = vec![0.0; w * h];
-        let mut mx_its = 0.0f64;
-        self.state.set_init();
-        let (iw, ih) = (w as i64, h as i64);
-        for i in 0..n {
-            self.apply_map_func();
-            if i < skip {continue;}
-            let (x, y) = self.state.get_xy();
-            let tw = (((x - wc) * m).round() as i64 + iw/2).clamp(0, iw-1) as usize;
-            let th = (((y - hc) * m).round() as i64 + ih/2).clamp(0, ih-1) as usize;
-            let val = &mut hist[th * w + tw];
-            *val += 1.0;
-            mx_its = mx_its.max(*val);
-        }
-        let inv_mx_its = 1.0 / mx_its;
-        self.img_vec = hist.into_iter().map(|v| v * inv_mx_its).collect::<Vec<_>>();
-    }
 }
 impl Attractor for Custom {
-    fn name(&self) -> &str {
-        &self.name
-    }
-    fn map_str(&self) -> &str {
-        &self.map_str
-    }
-    fn coef_ranges(&self) -> Vec<std::ops::RangeInclusive<f64>> {
-        self.range.clone()
-    }
-    fn speeds(&self) -> Vec<f64> {
-        self.speeds.clone()
-    }
-    fn coefs(&self) -> &[f64] {
-        &self.coefs
-    }
-    fn coefs_mut(&mut self) -> &mut [f64] {
-        &mut self.coefs
-    }
-    fn state(&self) -> &State {
-        &self.state
-    }
-    fn state_mut(&mut self) -> &mut State {
-        &mut self.state
-    }
-    fn change_random_coefs(&mut self) {
-        let mut rng = thread_rng();
-        self.coefs = self.range.iter()
-            .cloned()
-            .map(|r| rng.gen_range(r))
-            .collect::<Vec<f64>>();
-    }
-    fn param_changed(&mut self, flag: bool) {
-        self.param_changed = flag;
-    }
+    attractor_fields!();
     fn apply_map_func(&mut self) {
         let (x, y) = self.state.get_xy();
         if let Ok(ret) = self.expr.set_var("x", x).set_var("y", y).evals() {
             self.state.set_xy(ret[0], ret[1]);
         }
     }
-
-    fn gen_img(&mut self, n: usize, w: usize, h: usize, plt: &Palette) -> DynamicImage {
-        if self.param_changed {
-            self.gen_hist(n, w, h);
+    fn prepare(&mut self) -> bool {
+        self.expr = self.inner_expr.clone();
+        let vars = self.expr.vars();
+        for (v, c) in vars.iter().filter(|&v| v != "x" && v != "y").zip(self.coefs.iter()) {
+            self.expr.set_var(v, *c);
         }
-        
-        let factor = (10_000_000.0 / (n as f64)).sqrt() * ((w * h) as f64) / (1024.0 * 1024.0) * 100.;
-        let img = RgbImage::from_par_fn(w as u32, h as u32, |x, y| {
-            let v = self.img_vec[(y as usize) * w + (x as usize)];
-            let (r, g, b) = plt.get_col(v, v, factor);
-            Rgb([r, g, b])
-        }); 
-
-        DynamicImage::ImageRgb8(img)
+        self.expr.partial_eval().is_ok()
     }
-}
\ No newline at end of file
+}
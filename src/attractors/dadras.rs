@@ -0,0 +1,59 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dadras {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Dadras {
+    fn default() -> Self {
+        let range = vec![
+                (2.0..=4.0),(1.5..=3.5),(1.0..=2.5),(1.0..=3.0),(6.0..=12.0)
+            ];
+        Self {
+            name: "Dadras Attractor".into(),
+            map_str: "dx/dt = y - a0 * x + a1 * y * z, dy/dt = a2 * y - x * z + z, dz/dt = a3 * x * y - a4 * z".into(),
+            range,
+            speeds: vec![0.01; 5],
+            coefs: vec![3.0, 2.7, 1.7, 2.0, 9.0],
+            state: State::new(3, -3.0..=3.0, Some(0.002)).with_init(&[1.1, 2.1, -2.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Dadras {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        self.state.get_xy()
+    }
+}
+
+impl Flow for Dadras {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        dx[0] = x[1] - self.coefs[0] * x[0] + self.coefs[1] * x[1] * x[2];
+        dx[1] = self.coefs[2] * x[1] - x[0] * x[2] + x[2];
+        dx[2] = self.coefs[3] * x[0] * x[1] - self.coefs[4] * x[2];
+    }
+}
\ No newline at end of file
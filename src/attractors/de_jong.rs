@@ -0,0 +1,46 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeJong {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for DeJong {
+    fn default() -> Self {
+        let range = vec![
+                (-3.0..=3.0),(-3.0..=3.0),(-3.0..=3.0),(-3.0..=3.0)
+            ];
+        Self {
+            name: "De Jong Attractor".into(),
+            map_str: "x = sin(a0 * y) - cos(a1 * x), y = sin(a2 * x) - cos(a3 * y)".into(),
+            range,
+            speeds: vec![0.001; 4],
+            coefs: vec![1.4, -2.3, 2.4, -2.1],
+            state: State::new(2, -2.0..=2.0, None),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for DeJong {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        self.state.set_xy(
+            (self.coefs[0] * y).sin() - (self.coefs[1] * x).cos(),
+            (self.coefs[2] * x).sin() - (self.coefs[3] * y).cos()
+        );
+    }
+}
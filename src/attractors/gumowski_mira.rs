@@ -0,0 +1,49 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GumowskiMira {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for GumowskiMira {
+    fn default() -> Self {
+        let range = vec![
+                (0.0..=0.01),(0.0..=0.1),(-0.95..=-0.35)
+            ];
+        Self {
+            name: "Gumowski-Mira Attractor".into(),
+            map_str: "f(x) = a2 * x + 2 * (1 - a2) * x^2 / (1 + x^2), x = y + a0 * (1 - a1 * y^2) * y + f(x), y = -x + f(x_new)".into(),
+            range,
+            speeds: vec![0.0001, 0.0001, 0.001],
+            coefs: vec![0.008, 0.05, -0.496],
+            state: State::new(2, -1.0..=1.0, None).with_init(&[0.1, 0.1]),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for GumowskiMira {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        let mu = self.coefs[2];
+        let f = |x: f64| mu * x + 2.0 * (1.0 - mu) * x * x / (1.0 + x * x);
+        let x_new = y + self.coefs[0] * (1.0 - self.coefs[1] * y * y) * y + f(x);
+        self.state.set_xy(
+            x_new,
+            -x + f(x_new)
+        );
+    }
+}
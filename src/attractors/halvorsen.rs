@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Halvorsen {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Halvorsen {
+    fn default() -> Self {
+        let range = vec![
+                (1.0..=1.8)
+            ];
+        Self {
+            name: "Halvorsen Attractor".into(),
+            map_str: "dx/dt = -a0 * x - 4y - 4z - y^2, dy/dt = -a0 * y - 4z - 4x - z^2, dz/dt = -a0 * z - 4x - 4y - x^2".into(),
+            range,
+            speeds: vec![0.001],
+            coefs: vec![1.4],
+            state: State::new(3, -3.0..=3.0, Some(0.002)).with_init(&[-1.48, -1.51, 2.04]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Halvorsen {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        self.state.get_xy()
+    }
+}
+
+impl Flow for Halvorsen {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        let a = self.coefs[0];
+        dx[0] = -a * x[0] - 4.0 * x[1] - 4.0 * x[2] - x[1] * x[1];
+        dx[1] = -a * x[1] - 4.0 * x[2] - 4.0 * x[0] - x[2] * x[2];
+        dx[2] = -a * x[2] - 4.0 * x[0] - 4.0 * x[1] - x[0] * x[0];
+    }
+}
\ No newline at end of file
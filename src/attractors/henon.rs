@@ -0,0 +1,46 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Henon {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Henon {
+    fn default() -> Self {
+        let range = vec![
+                (1.15..=1.4),(0.2..=0.3)
+            ];
+        Self {
+            name: "Henon Attractor".into(),
+            map_str: "x = 1 - a0 * x^2 + y, y = a1 * x".into(),
+            range,
+            speeds: vec![0.0001; 2],
+            coefs: vec![1.4, 0.3],
+            state: State::new(2, -0.5..=0.5, None).with_init(&[0.1, 0.1]),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for Henon {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        self.state.set_xy(
+            1.0 - self.coefs[0] * x * x + y,
+            self.coefs[1] * x
+        );
+    }
+}
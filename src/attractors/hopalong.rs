@@ -0,0 +1,46 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hopalong {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Hopalong {
+    fn default() -> Self {
+        let range = vec![
+                (-10.0..=10.0),(-10.0..=10.0),(-10.0..=10.0)
+            ];
+        Self {
+            name: "Hopalong Attractor".into(),
+            map_str: "x = y - sign(x) * sqrt(|a1 * x - a2|), y = a0 - x".into(),
+            range,
+            speeds: vec![0.01; 3],
+            coefs: vec![7.17, 8.44, 2.56],
+            state: State::new(2, -1.0..=1.0, None).with_init(&[0.0, 0.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for Hopalong {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        self.state.set_xy(
+            y - x.signum() * (self.coefs[1] * x - self.coefs[2]).abs().sqrt(),
+            self.coefs[0] - x
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ikeda {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Ikeda {
+    fn default() -> Self {
+        let range = vec![
+                (0.84..=0.9),(0.39..=0.41),(5.9..=6.1)
+            ];
+        Self {
+            name: "Ikeda Attractor".into(),
+            map_str: "t = a1 - a2 / (1 + x^2 + y^2), x = 1 + a0 * (x * cos(t) - y * sin(t)), y = a0 * (x * sin(t) + y * cos(t))".into(),
+            range,
+            speeds: vec![0.0001, 0.001, 0.01],
+            coefs: vec![0.88, 0.4, 6.0],
+            state: State::new(2, -0.5..=0.5, None).with_init(&[0.1, 0.1]),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for Ikeda {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        let t = self.coefs[1] - self.coefs[2] / (1.0 + x * x + y * y);
+        let (sin, cos) = t.sin_cos();
+        self.state.set_xy(
+            1.0 + self.coefs[0] * (x * cos - y * sin),
+            self.coefs[0] * (x * sin + y * cos)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lu {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Lu {
+    fn default() -> Self {
+        let range = vec![
+                (30.0..=40.0),(1.0..=5.0),(13.0..=25.0)
+            ];
+        Self {
+            name: "Lu Attractor".into(),
+            map_str: "dx/dt = a0 * (y - x), dy/dt = -x * z + a2 * y, dz/dt = x * y - a1 * z".into(),
+            range,
+            speeds: vec![0.01; 3],
+            coefs: vec![36.0, 3.0, 20.0],
+            state: State::new(3, -10.0..=10.0, Some(0.001)).with_init(&[1.0, 1.0, 1.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Lu {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        let (x, _, z) = self.state.get_xyz();
+        (x, z)
+    }
+}
+
+impl Flow for Lu {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        dx[0] = self.coefs[0] * (x[1] - x[0]);
+        dx[1] = -x[0] * x[2] + self.coefs[2] * x[1];
+        dx[2] = x[0] * x[1] - self.coefs[1] * x[2];
+    }
+}
\ No newline at end of file
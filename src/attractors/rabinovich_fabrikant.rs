@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RabinovichFabrikant {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for RabinovichFabrikant {
+    fn default() -> Self {
+        let range = vec![
+                (0.9..=1.3),(0.7..=1.0)
+            ];
+        Self {
+            name: "Rabinovich-Fabrikant Attractor".into(),
+            map_str: "dx/dt = y * (z - 1 + x^2) + a1 * x, dy/dt = x * (3z + 1 - x^2) + a1 * y, dz/dt = -2z * (a0 + x * y)".into(),
+            range,
+            speeds: vec![0.001; 2],
+            coefs: vec![1.1, 0.87],
+            state: State::new(3, -1.0..=1.0, Some(0.002)).with_init(&[-1.0, 0.0, 0.5]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for RabinovichFabrikant {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        self.state.get_xy()
+    }
+}
+
+impl Flow for RabinovichFabrikant {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        let (alpha, gamma) = (self.coefs[0], self.coefs[1]);
+        dx[0] = x[1] * (x[2] - 1.0 + x[0] * x[0]) + gamma * x[0];
+        dx[1] = x[0] * (3.0 * x[2] + 1.0 - x[0] * x[0]) + gamma * x[1];
+        dx[2] = -2.0 * x[2] * (alpha + x[0] * x[1]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,59 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rossler {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Rossler {
+    fn default() -> Self {
+        let range = vec![
+                (0.0..=0.4),(0.0..=2.0),(2.0..=14.0)
+            ];
+        Self {
+            name: "Rossler Attractor".into(),
+            map_str: "dx/dt = -y - z, dy/dt = x + a0 * y, dz/dt = a1 + z * (x - a2)".into(),
+            range,
+            speeds: vec![0.001, 0.001, 0.01],
+            coefs: vec![0.2, 0.2, 5.7],
+            state: State::new(3, -5.0..=5.0, Some(0.005)).with_init(&[1.0, 1.0, 0.0]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Rossler {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        self.state.get_xy()
+    }
+}
+
+impl Flow for Rossler {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        dx[0] = -x[1] - x[2];
+        dx[1] = x[0] + self.coefs[0] * x[1];
+        dx[2] = self.coefs[1] + x[2] * (x[0] - self.coefs[2]);
+    }
+}
\ No newline at end of file
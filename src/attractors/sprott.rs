@@ -0,0 +1,186 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+// the simple chaotic flows A to S of J. C. Sprott, Phys. Rev. E 50 (1994). each has one constant
+// exposed as a0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SprottCase {
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S,
+}
+
+impl SprottCase {
+    pub const ALL: [SprottCase; 19] = [
+        SprottCase::A, SprottCase::B, SprottCase::C, SprottCase::D, SprottCase::E, SprottCase::F, SprottCase::G,
+        SprottCase::H, SprottCase::I, SprottCase::J, SprottCase::K, SprottCase::L, SprottCase::M, SprottCase::N,
+        SprottCase::O, SprottCase::P, SprottCase::Q, SprottCase::R, SprottCase::S,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            SprottCase::A => "Sprott A",
+            SprottCase::B => "Sprott B",
+            SprottCase::C => "Sprott C",
+            SprottCase::D => "Sprott D",
+            SprottCase::E => "Sprott E",
+            SprottCase::F => "Sprott F",
+            SprottCase::G => "Sprott G",
+            SprottCase::H => "Sprott H",
+            SprottCase::I => "Sprott I",
+            SprottCase::J => "Sprott J",
+            SprottCase::K => "Sprott K",
+            SprottCase::L => "Sprott L",
+            SprottCase::M => "Sprott M",
+            SprottCase::N => "Sprott N",
+            SprottCase::O => "Sprott O",
+            SprottCase::P => "Sprott P",
+            SprottCase::Q => "Sprott Q",
+            SprottCase::R => "Sprott R",
+            SprottCase::S => "Sprott S",
+        }
+    }
+    pub fn map_str(&self) -> &'static str {
+        match self {
+            SprottCase::A => "dx/dt = y, dy/dt = -x + y * z, dz/dt = a0 - y^2",
+            SprottCase::B => "dx/dt = y * z, dy/dt = x - y, dz/dt = a0 - x * y",
+            SprottCase::C => "dx/dt = y * z, dy/dt = x - y, dz/dt = a0 - x^2",
+            SprottCase::D => "dx/dt = -y, dy/dt = x + z, dz/dt = x * z + a0 * y^2",
+            SprottCase::E => "dx/dt = y * z, dy/dt = x^2 - y, dz/dt = 1 - a0 * x",
+            SprottCase::F => "dx/dt = y + z, dy/dt = -x + a0 * y, dz/dt = x^2 - z",
+            SprottCase::G => "dx/dt = a0 * x + z, dy/dt = x * z - y, dz/dt = -x + y",
+            SprottCase::H => "dx/dt = -y + z^2, dy/dt = x + a0 * y, dz/dt = x - z",
+            SprottCase::I => "dx/dt = -a0 * y, dy/dt = x + z, dz/dt = x + y^2 - z",
+            SprottCase::J => "dx/dt = a0 * z, dy/dt = -2y + z, dz/dt = -x + y + y^2",
+            SprottCase::K => "dx/dt = x * y - z, dy/dt = x - y, dz/dt = x + a0 * z",
+            SprottCase::L => "dx/dt = y + a0 * z, dy/dt = 0.9x^2 - y, dz/dt = 1 - x",
+            SprottCase::M => "dx/dt = -z, dy/dt = -x^2 - y, dz/dt = a0 * (1 + x) + y",
+            SprottCase::N => "dx/dt = -a0 * y, dy/dt = x + z^2, dz/dt = 1 + y - 2z",
+            SprottCase::O => "dx/dt = y, dy/dt = x - z, dz/dt = x + x * z + a0 * y",
+            SprottCase::P => "dx/dt = a0 * y + z, dy/dt = -x + y^2, dz/dt = x + y",
+            SprottCase::Q => "dx/dt = -z, dy/dt = x - y, dz/dt = a0 * x + y^2 + 0.5z",
+            SprottCase::R => "dx/dt = a0 - y, dy/dt = 0.4 + z, dz/dt = x * y - z",
+            SprottCase::S => "dx/dt = -x - a0 * y, dy/dt = x + z^2, dz/dt = 1 + x",
+        }
+    }
+    // a point on the attractor, the orbit does not pass through a transient first
+    fn init(&self) -> [f64; 3] {
+        match self {
+            SprottCase::A => [0.0, 5.0, 0.0],
+            SprottCase::B => [0.0, -0.17, 0.87],
+            SprottCase::C => [0.6, 1.46, -1.71],
+            SprottCase::D => [-0.8, 0.6, 1.24],
+            SprottCase::E => [-0.19, 0.49, 2.42],
+            SprottCase::F => [-0.25, -0.24, 0.05],
+            SprottCase::G => [-2.06, 0.76, -0.36],
+            SprottCase::H => [0.17, 0.64, 0.03],
+            SprottCase::I => [0.06, 0.14, 0.13],
+            SprottCase::J => [0.49, -5.03, -9.2],
+            SprottCase::K => [-0.87, -1.36, 0.23],
+            SprottCase::L => [3.92, 18.51, -4.56],
+            SprottCase::M => [-0.84, -0.94, 1.18],
+            SprottCase::N => [-9.79, -15.05, -4.17],
+            SprottCase::O => [-0.75, -0.24, -1.49],
+            SprottCase::P => [-0.23, 0.25, -0.27],
+            SprottCase::Q => [-0.24, -1.07, -1.66],
+            SprottCase::R => [3.26, 2.07, 2.69],
+            SprottCase::S => [-1.55, -0.17, 0.41],
+        }
+    }
+    // the constant of the paper and the range of a0 around it
+    fn coef(&self) -> (f64, std::ops::RangeInclusive<f64>) {
+        match self {
+            SprottCase::A | SprottCase::B | SprottCase::C => (1.0, 0.5..=1.5),
+            SprottCase::D => (3.0, 2.0..=4.0),
+            SprottCase::E => (4.0, 3.0..=5.0),
+            SprottCase::F | SprottCase::H => (0.5, 0.3..=0.7),
+            SprottCase::G => (0.4, 0.2..=0.6),
+            SprottCase::I => (0.2, 0.1..=0.3),
+            SprottCase::J | SprottCase::N => (2.0, 1.5..=2.5),
+            SprottCase::K => (0.3, 0.1..=0.5),
+            SprottCase::L => (3.9, 3.0..=4.5),
+            SprottCase::M => (1.7, 1.2..=2.2),
+            SprottCase::O | SprottCase::P => (2.7, 2.2..=3.2),
+            SprottCase::Q => (3.1, 2.5..=3.5),
+            SprottCase::R => (0.9, 0.6..=1.2),
+            SprottCase::S => (4.0, 3.0..=5.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sprott {
+    pub name: String,
+    pub map_str: String,
+    pub case: SprottCase,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Sprott {
+    fn default() -> Self {
+        Self::with_case(SprottCase::A)
+    }
+}
+impl Sprott {
+    pub fn with_case(case: SprottCase) -> Self {
+        let (coef, range) = case.coef();
+        Self {
+            name: case.name().into(),
+            map_str: case.map_str().into(),
+            case,
+            range: vec![range],
+            speeds: vec![0.001],
+            coefs: vec![coef],
+            state: State::new(3, -1.0..=1.0, Some(0.005)).with_init(&case.init()),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Sprott {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+}
+
+impl Flow for Sprott {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        let a = self.coefs[0];
+        let (x, y, z) = (x[0], x[1], x[2]);
+        let d = match self.case {
+            SprottCase::A => [y, -x + y * z, a - y * y],
+            SprottCase::B => [y * z, x - y, a - x * y],
+            SprottCase::C => [y * z, x - y, a - x * x],
+            SprottCase::D => [-y, x + z, x * z + a * y * y],
+            SprottCase::E => [y * z, x * x - y, 1.0 - a * x],
+            SprottCase::F => [y + z, -x + a * y, x * x - z],
+            SprottCase::G => [a * x + z, x * z - y, -x + y],
+            SprottCase::H => [-y + z * z, x + a * y, x - z],
+            SprottCase::I => [-a * y, x + z, x + y * y - z],
+            SprottCase::J => [a * z, -2.0 * y + z, -x + y + y * y],
+            SprottCase::K => [x * y - z, x - y, x + a * z],
+            SprottCase::L => [y + a * z, 0.9 * x * x - y, 1.0 - x],
+            SprottCase::M => [-z, -x * x - y, a * (1.0 + x) + y],
+            SprottCase::N => [-a * y, x + z * z, 1.0 + y - 2.0 * z],
+            SprottCase::O => [y, x - z, x + x * z + a * y],
+            SprottCase::P => [a * y + z, -x + y * y, x + y],
+            SprottCase::Q => [-z, x - y, a * x + y * y + 0.5 * z],
+            SprottCase::R => [a - y, 0.4 + z, x * y - z],
+            SprottCase::S => [-x - a * y, x + z * z, 1.0 + x],
+        };
+        dx.copy_from_slice(&d);
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+use crate::integrator::Flow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thomas {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Thomas {
+    fn default() -> Self {
+        let range = vec![
+                (0.1..=0.3)
+            ];
+        Self {
+            name: "Thomas Attractor".into(),
+            map_str: "dx/dt = sin(y) - a0 * x, dy/dt = sin(z) - a0 * y, dz/dt = sin(x) - a0 * z".into(),
+            range,
+            speeds: vec![0.0001],
+            coefs: vec![0.208186],
+            state: State::new(3, -3.0..=3.0, Some(0.02)).with_init(&[1.1, 1.1, -0.01]),
+            img_vec: ImageCache::default(),
+            param_changed: true
+        }
+    }
+}
+impl Attractor for Thomas {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let dt = self.state().get_dt().unwrap();
+        let (t, integrator) = (self.state.time, self.state.integrator());
+        let mut x = self.state.take_xs();
+        integrator.step(self, t, &mut x, dt);
+        self.state.set_xs(x);
+        self.state.time += dt;
+    }
+    fn project(&self) -> (f64, f64) {
+        self.state.get_xy()
+    }
+}
+
+impl Flow for Thomas {
+    fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+        dx[0] = x[1].sin() - self.coefs[0] * x[0];
+        dx[1] = x[2].sin() - self.coefs[0] * x[1];
+        dx[2] = x[0].sin() - self.coefs[0] * x[2];
+    }
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+use serde::{Serialize, Deserialize};
+
+use super::attractor::{Attractor, ImageCache, attractor_fields};
+use crate::state::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tinkerbell {
+    pub name: String,
+    pub map_str: String,
+    pub range: Vec<std::ops::RangeInclusive<f64>>,
+    pub speeds: Vec<f64>,
+    pub coefs: Vec<f64>,
+    pub state: State,
+    #[serde(skip)]
+    pub img_vec: ImageCache,
+    #[serde(skip)]
+    pub param_changed: bool,
+}
+
+impl Default for Tinkerbell {
+    fn default() -> Self {
+        let range = vec![
+                (0.85..=0.9),(-0.62..=-0.58),(1.95..=2.05),(0.45..=0.5)
+            ];
+        Self {
+            name: "Tinkerbell Attractor".into(),
+            map_str: "x = x^2 - y^2 + a0 * x + a1 * y, y = 2 * x * y + a2 * x + a3 * y".into(),
+            range,
+            speeds: vec![0.0001; 4],
+            coefs: vec![0.9, -0.6013, 2.0, 0.5],
+            state: State::new(2, -0.5..=0.5, None).with_init(&[-0.72, -0.64]),
+            img_vec: ImageCache::default(),
+            param_changed: true,
+        }
+    }
+}
+impl Attractor for Tinkerbell {
+    attractor_fields!();
+    fn apply_map_func(&mut self) {
+        let (x, y) = self.state.get_xy();
+        self.state.set_xy(
+            x * x - y * y + self.coefs[0] * x + self.coefs[1] * y,
+            2.0 * x * y + self.coefs[2] * x + self.coefs[3] * y
+        );
+    }
+}
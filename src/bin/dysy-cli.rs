@@ -0,0 +1,6 @@
+fn main() {
+    if let Err(e) = dysy::cli::run(std::env::args().skip(1)) {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Serialize, Deserialize};
+use std::f64::consts::{PI, FRAC_PI_2};
+
+// distance of the eye from the pivot in units of the radius of the attractor
+const EYE_DISTANCE: f64 = 3.0;
+
+// orbit camera for states of three or more dimensions, looking at the first three components.
+// yaw rotates around the third axis and pitch tilts towards it; the default looks along the
+// second axis and shows the first and third components, the x-z view of the Lorenz attractor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Camera {
+    pub yaw: f64,
+    pub pitch: f64,
+    pub zoom: f64,
+    pub perspective: bool,
+    // center and radius of the attractor, set when the bounding box is searched
+    #[serde(skip)]
+    pivot: Option<([f64; 3], f64)>,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            zoom: 1.0,
+            perspective: false,
+            pivot: None,
+        }
+    }
+}
+
+impl Camera {
+    pub fn set_pivot(&mut self, center: [f64; 3], radius: f64) {
+        self.pivot = Some((center, if radius > 0.0 {radius} else {1.0}));
+    }
+    // rotate by a mouse drag in radians, yaw wraps around and pitch stops at the poles
+    pub fn rotate(&mut self, d_yaw: f64, d_pitch: f64) {
+        self.yaw = (self.yaw + d_yaw + PI).rem_euclid(2.0 * PI) - PI;
+        self.pitch = (self.pitch + d_pitch).clamp(-FRAC_PI_2, FRAC_PI_2);
+    }
+    // half size of the visible square in projected units, independent of the angles so the image
+    // does not jump while rotating
+    pub fn extent(&self) -> f64 {
+        1.0 / self.zoom.max(1e-6)
+    }
+    // screen coordinates relative to the pivot in units of its radius, NaN behind the eye
+    pub fn project(&self, x: f64, y: f64, z: f64) -> (f64, f64) {
+        let ([cx, cy, cz], r) = self.pivot.unwrap_or(([0.0; 3], 1.0));
+        let (x, y, z) = ((x - cx) / r, (y - cy) / r, (z - cz) / r);
+        let (sy, cyw) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        let (x1, y1) = (x * cyw - y * sy, x * sy + y * cyw);
+        let (depth, up) = (y1 * cp - z * sp, y1 * sp + z * cp);
+        if !self.perspective {return (x1, up);}
+        let d = EYE_DISTANCE + depth;
+        if d <= 1e-3 {return (f64::NAN, f64::NAN);}
+        let f = EYE_DISTANCE / d;
+        (x1 * f, up * f)
+    }
+}
@@ -0,0 +1,314 @@
+use crate::attractors::Attractor;
+use crate::params;
+use crate::render;
+use crate::export;
+use crate::animation;
+use crate::util::Palette;
+use anyhow::{Result, anyhow, Context};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::fs;
+use std::io::BufReader;
+use std::time;
+
+const USAGE: &str = "\
+usage: dysy-cli render --params <FILE.json> --output <FILE.png> [options]
+       dysy-cli export --params <FILE.json> --output <FILE.csv|FILE.npy> [export options]
+       dysy-cli animate --key <FILE.json> --key <FILE.json> [--key ...] --output <FILE> [animate options]
+
+options:
+    --params <FILE>     parameter JSON written by \"Save Params\", or a PNG saved by dysy
+    --output <FILE>     output image path
+    --palette <VALUE>   \"default\", \"random\" or a palette JSON file (default: the palette saved
+                        with --params, else default)
+    --iter <N>          number of iterations (default: the one saved with --params, else 10000000)
+    --width <W>         image width in pixels (default: the one saved with --params, else 1024)
+    --height <H>        image height in pixels (default: the one saved with --params, else 1024)
+    --scale-iter        treat --iter as the count for 1024x1024 and scale it with the image size
+    --tiled             render in horizontal tiles streamed to a PNG file, for very large images.
+                        every tile replays all --iter iterations, so it runs tiles x --iter in total
+    --tile-rows <N>     rows per tile of --tiled (default: 64M pixels per tile)
+    -h, --help          print this help
+
+export options:
+    --params <FILE>     parameter JSON written by \"Save Params\", or a PNG saved by dysy
+    --output <FILE>     trajectory file, the format follows the extension (.csv or .npy)
+    --steps <N>         number of rows (default: 1000000)
+    --skip <N>          iterations dropped before the first row (default: 0)
+    --stride <N>        iterations per row (default: 1)
+    --projected         add the 2D point of the image (px, py) after the state
+
+animate options:
+    --key <FILE>        keyframe, a parameter JSON or a PNG saved by dysy. all keys must be of the same
+                        system, they are spread evenly over the animation in the order given
+    --output <FILE>     .gif, .apng, or .png for a sequence of numbered PNGs next to it
+    --frames <N>        number of frames (default: 60)
+    --fps <N>           frames per second (default: 30)
+    --linear            interpolate linearly instead of easing in and out of every key
+    --iter <N>          iterations per frame (default: 1000000)
+    --width <W>         frame width in pixels (default: 512)
+    --height <H>        frame height in pixels (default: 512)";
+
+struct RenderArgs {
+    params: PathBuf,
+    output: PathBuf,
+    palette: Option<String>,
+    num_iter: Option<usize>,
+    width: Option<usize>,
+    height: Option<usize>,
+    scale_iter: bool,
+    tiled: bool,
+    tile_rows: Option<usize>,
+}
+
+impl RenderArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>> {
+        let (mut params, mut output) = (None, None);
+        let mut palette = None;
+        let (mut num_iter, mut width, mut height) = (None, None, None);
+        let mut scale_iter = false;
+        let (mut tiled, mut tile_rows) = (false, None);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("missing value for {}", arg));
+            match arg.as_str() {
+                "--params" => params = Some(PathBuf::from(value()?)),
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--palette" => palette = Some(value()?),
+                "--iter" => num_iter = Some(positive(value()?, &arg)?),
+                "--width" => width = Some(positive(value()?, &arg)?),
+                "--height" => height = Some(positive(value()?, &arg)?),
+                "--scale-iter" => scale_iter = true,
+                "--tiled" => tiled = true,
+                "--tile-rows" => tile_rows = Some(positive(value()?, &arg)?),
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(anyhow!("unknown option {}\n\n{}", arg, USAGE)),
+            }
+        }
+        Ok(Some(Self {
+            params: params.ok_or_else(|| anyhow!("--params is required\n\n{}", USAGE))?,
+            output: output.ok_or_else(|| anyhow!("--output is required\n\n{}", USAGE))?,
+            palette,
+            num_iter,
+            width,
+            height,
+            scale_iter,
+            tiled: tiled || tile_rows.is_some(),
+            tile_rows,
+        }))
+    }
+}
+
+struct ExportArgs {
+    params: PathBuf,
+    output: PathBuf,
+    options: export::ExportOptions,
+}
+
+impl ExportArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>> {
+        let (mut params, mut output) = (None, None);
+        let mut options = export::ExportOptions::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("missing value for {}", arg));
+            match arg.as_str() {
+                "--params" => params = Some(PathBuf::from(value()?)),
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--steps" => options.steps = positive(value()?, &arg)?,
+                "--skip" => options.skip = value()?.parse().context("invalid --skip")?,
+                "--stride" => options.stride = positive(value()?, &arg)?,
+                "--projected" => options.projected = true,
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(anyhow!("unknown option {}\n\n{}", arg, USAGE)),
+            }
+        }
+        let output: PathBuf = output.ok_or_else(|| anyhow!("--output is required\n\n{}", USAGE))?;
+        options.format = export::ExportFormat::from_path(&output)
+            .ok_or_else(|| anyhow!("unknown trajectory format {}, use .csv or .npy", output.display()))?;
+        Ok(Some(Self {
+            params: params.ok_or_else(|| anyhow!("--params is required\n\n{}", USAGE))?,
+            output,
+            options,
+        }))
+    }
+}
+
+struct AnimateArgs {
+    keys: Vec<PathBuf>,
+    output: PathBuf,
+    options: animation::AnimationOptions,
+}
+
+impl AnimateArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>> {
+        let (mut keys, mut output) = (vec![], None);
+        let mut options = animation::AnimationOptions::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("missing value for {}", arg));
+            match arg.as_str() {
+                "--key" => keys.push(PathBuf::from(value()?)),
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--frames" => options.frames = positive(value()?, &arg)?,
+                "--fps" => options.fps = positive(value()?, &arg)?,
+                "--linear" => options.easing = animation::Easing::Linear,
+                "--iter" => options.num_iter = positive(value()?, &arg)?,
+                "--width" => options.width = positive(value()?, &arg)?,
+                "--height" => options.height = positive(value()?, &arg)?,
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(anyhow!("unknown option {}\n\n{}", arg, USAGE)),
+            }
+        }
+        if keys.is_empty() {
+            return Err(anyhow!("--key is required\n\n{}", USAGE));
+        }
+        let output: PathBuf = output.ok_or_else(|| anyhow!("--output is required\n\n{}", USAGE))?;
+        options.format = animation::AnimationFormat::from_path(&output)
+            .ok_or_else(|| anyhow!("unknown animation format {}, use .gif, .apng or .png", output.display()))?;
+        Ok(Some(Self { keys, output, options }))
+    }
+}
+
+// a count or size option, which must be above zero
+fn positive<T>(value: String, option: &str) -> Result<T>
+where
+    T: FromStr + Default + PartialEq,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let n: T = value.parse().with_context(|| format!("invalid {}", option))?;
+    if n == T::default() {
+        return Err(anyhow!("{} must be positive\n\n{}", option, USAGE));
+    }
+    Ok(n)
+}
+
+fn load_palette(value: &str) -> Result<Palette> {
+    match value {
+        "default" => Ok(Palette::default()),
+        "random" => Ok(Palette::new()),
+        path => {
+            let file = fs::File::open(path).with_context(|| format!("cannot open palette {}", path))?;
+            Ok(serde_json::from_reader(BufReader::new(file))?)
+        }
+    }
+}
+
+fn render(args: RenderArgs) -> Result<()> {
+    // the settings saved with the parameters: a PNG has the iterations it was rendered with,
+    // a parameter JSON the render settings of the GUI
+    let (mut attractor, embedded, saved_iter, saved_size) = if params::is_png(&args.params) {
+        let image = params::load_png_params(&args.params)
+            .with_context(|| format!("cannot load params {}", args.params.display()))?;
+        (image.attractor, image.palette, image.num_iter.map(|n| (n, false)), image.size)
+    }
+    else {
+        let document = params::load_document(&args.params)
+            .with_context(|| format!("cannot load params {}", args.params.display()))?;
+        let render = document.render;
+        (
+            document.attractor,
+            document.palette,
+            render.as_ref().map(|r| (r.num_iter_high, r.scale_iter)),
+            render.as_ref().map(|r| (r.width, r.height)),
+        )
+    };
+    let palette = match (&args.palette, embedded) {
+        (None, Some(palette)) => palette,
+        (value, _) => load_palette(value.as_deref().unwrap_or("default"))?,
+    };
+
+    let width = args.width.or(saved_size.map(|s| s.0)).unwrap_or(1024);
+    let height = args.height.or(saved_size.map(|s| s.1)).unwrap_or(1024);
+    let (num_iter, scale_iter) = match args.num_iter {
+        Some(n) => (n, args.scale_iter),
+        None => saved_iter.map_or((10000000, args.scale_iter), |(n, scale)| (n, scale || args.scale_iter)),
+    };
+    let num_iter = if scale_iter {render::scaled_iters(num_iter, width, height)} else {num_iter};
+
+    let start = time::Instant::now();
+    if args.tiled {
+        let tile_rows = args.tile_rows.unwrap_or_else(|| render::tiled::default_tile_rows(width));
+        let progress = render::Progress::default();
+        render::tiled::save_tiled(
+            attractor.as_mut(), &args.output, num_iter, width, height, tile_rows, &palette, &progress
+        ).with_context(|| format!("cannot save image {}", args.output.display()))?;
+    }
+    else {
+        attractor.param_changed(true);
+        attractor.save_img(&args.output, num_iter, width, height, &palette)
+            .with_context(|| format!("cannot save image {}", args.output.display()))?;
+    }
+    eprintln!(
+        "{} {:?} -> {} ({:.3} sec)",
+        attractor.name(), attractor.coefs(), args.output.display(), start.elapsed().as_secs_f32()
+    );
+    Ok(())
+}
+
+fn export(args: ExportArgs) -> Result<()> {
+    let mut attractor = params::load_attractor(&args.params)
+        .with_context(|| format!("cannot load params {}", args.params.display()))?;
+    let start = time::Instant::now();
+    let rows = export::export_trajectory(attractor.as_mut(), &args.output, &args.options)
+        .with_context(|| format!("cannot export trajectory {}", args.output.display()))?;
+    if rows < args.options.steps {
+        eprintln!("the orbit diverged after {} rows", rows);
+    }
+    eprintln!(
+        "{} {:?} -> {} ({} rows, {:.3} sec)",
+        attractor.name(), attractor.coefs(), args.output.display(), rows, start.elapsed().as_secs_f32()
+    );
+    Ok(())
+}
+
+fn animate(args: AnimateArgs) -> Result<()> {
+    let mut attractor: Option<Box<dyn Attractor>> = None;
+    let mut keys = vec![];
+    for path in &args.keys {
+        let (at, palette) = if params::is_png(path) {
+            let image = params::load_png_params(path)
+                .with_context(|| format!("cannot load key {}", path.display()))?;
+            (image.attractor, image.palette)
+        }
+        else {
+            let document = params::load_document(path)
+                .with_context(|| format!("cannot load key {}", path.display()))?;
+            (document.attractor, document.palette)
+        };
+        if let Some(first) = &attractor {
+            if first.name() != at.name() {
+                return Err(anyhow!("key {} is {}, not {}", path.display(), at.name(), first.name()));
+            }
+        }
+        keys.push(animation::Keyframe::new(0.0, at.as_ref(), &palette.unwrap_or_default()));
+        attractor.get_or_insert(at);
+    }
+    let Some(mut attractor) = attractor else {return Err(anyhow!("no keys"));};
+    animation::space_evenly(&mut keys);
+
+    let start = time::Instant::now();
+    let progress = render::Progress::default();
+    let frames = animation::render_animation(attractor.as_mut(), &keys, &args.options, &args.output, &progress)
+        .with_context(|| format!("cannot save animation {}", args.output.display()))?;
+    eprintln!(
+        "{} {} keys -> {} ({} frames, {:.3} sec)",
+        attractor.name(), keys.len(), args.output.display(), frames, start.elapsed().as_secs_f32()
+    );
+    Ok(())
+}
+
+fn print_usage() -> Result<()> {
+    println!("{}", USAGE);
+    Ok(())
+}
+
+// entry point of the headless renderer, `args` excludes the program name
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    match args.next().as_deref() {
+        // the parsers give None for -h or --help
+        Some("render") => RenderArgs::parse(args)?.map_or_else(print_usage, render),
+        Some("export") => ExportArgs::parse(args)?.map_or_else(print_usage, export),
+        Some("animate") => AnimateArgs::parse(args)?.map_or_else(print_usage, animate),
+        Some("-h") | Some("--help") => print_usage(),
+        Some(cmd) => Err(anyhow!("unknown command {}\n\n{}", cmd, USAGE)),
+        None => Err(anyhow!("no command given\n\n{}", USAGE)),
+    }
+}
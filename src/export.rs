@@ -0,0 +1,188 @@
+use anyhow::{Result, anyhow};
+use std::fs::File;
+use std::io::{self, BufWriter, Write, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::attractors::Attractor;
+use crate::render;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    // comma separated text with a header row
+    Csv,
+    // NumPy .npy of a (rows, columns) float64 array
+    Npy,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Npy];
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Npy => "NumPy",
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Npy => "npy",
+        }
+    }
+    // the format of the extension of `path`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Self::ALL.into_iter().find(|f| f.extension() == ext)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub steps: usize,
+    // iterations dropped before the first row
+    pub skip: usize,
+    // iterations per row
+    pub stride: usize,
+    // adds the 2D point of the view of the trajectory image
+    pub projected: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Csv,
+            steps: 1000000,
+            skip: 0,
+            stride: 1,
+            projected: false,
+        }
+    }
+}
+
+// time for flows and the iteration for maps, the components of the state, then the projected point
+pub fn column_names<A: Attractor + ?Sized>(at: &A, projected: bool) -> Vec<String> {
+    let time = if at.state().get_dt().is_some() {"t"} else {"n"};
+    let mut names = vec![time.to_string()];
+    names.extend((0..at.state().get_xs().len()).map(|i| format!("x{}", i)));
+    if projected {
+        names.extend(["px".to_string(), "py".to_string()]);
+    }
+    names
+}
+
+// iterates `at` and hands every row to `write`, stops early if the orbit diverges
+fn write_rows<A: Attractor + ?Sized>(
+    at: &mut A, opts: &ExportOptions, mut write: impl FnMut(&[f64]) -> io::Result<()>
+) -> io::Result<usize> {
+    at.state_mut().set_init();
+    let flow = at.state().get_dt().is_some();
+    let mut iters = 0;
+    for _ in 0..opts.skip {
+        at.apply_map_func();
+    }
+    let mut row = vec![];
+    for rows in 0..opts.steps {
+        for _ in 0..opts.stride.max(1) {
+            at.apply_map_func();
+            iters += 1;
+        }
+        let x = at.state().get_xs();
+        if x.iter().any(|v| !v.is_finite()) {
+            at.state_mut().set_init();
+            return Ok(rows);
+        }
+        row.clear();
+        row.push(if flow {at.state().time} else {(opts.skip + iters) as f64});
+        row.extend_from_slice(x);
+        if opts.projected {
+            let (px, py) = at.view();
+            row.extend([px, py]);
+        }
+        write(&row)?;
+    }
+    at.state_mut().set_init();
+    Ok(opts.steps)
+}
+
+// header of a version 1.0 .npy file of little endian float64 rows. it has the same length for any
+// number of rows, so the count can be written once the orbit is done
+fn npy_header(rows: usize, columns: usize) -> Vec<u8> {
+    let dict = |rows: usize| format!("{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}", rows, columns);
+    // the data starts at a multiple of 64 bytes
+    let len = (10 + dict(usize::MAX).len() + 1).div_ceil(64) * 64;
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend(((len - 10) as u16).to_le_bytes());
+    header.extend(format!("{:<width$}\n", dict(rows), width = len - 11).bytes());
+    header
+}
+
+// streams the trajectory of `at` to `path` row by row, returns the number of rows written
+pub fn export_trajectory<A: Attractor + ?Sized>(at: &mut A, path: &Path, opts: &ExportOptions) -> Result<usize> {
+    if !at.prepare() {
+        return Err(anyhow!("{} cannot be iterated", at.name()));
+    }
+    // the camera projects around the center of the attractor found by the viewport
+    if opts.projected && at.state().camera().is_some() {
+        render::fit_viewport(at, opts.steps, 1, 1);
+    }
+    let columns = column_names(at, opts.projected);
+    let mut writer = BufWriter::new(File::create(path)?);
+    let rows = match opts.format {
+        ExportFormat::Csv => {
+            writeln!(writer, "{}", columns.join(","))?;
+            let rows = write_rows(at, opts, |row| {
+                for (i, v) in row.iter().enumerate() {
+                    if i > 0 {writer.write_all(b",")?;}
+                    write!(writer, "{}", v)?;
+                }
+                writer.write_all(b"\n")
+            })?;
+            writer.flush()?;
+            rows
+        },
+        ExportFormat::Npy => {
+            writer.write_all(&npy_header(0, columns.len()))?;
+            let rows = write_rows(at, opts, |row| {
+                row.iter().try_for_each(|v| writer.write_all(&v.to_le_bytes()))
+            })?;
+            let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&npy_header(rows, columns.len()))?;
+            rows
+        },
+    };
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attractors::Henon;
+
+    #[test]
+    fn npy_header_length() {
+        for (rows, columns) in [(0, 3), (1000000, 3), (usize::MAX, 12)] {
+            let header = npy_header(rows, columns);
+            assert_eq!(header.len() % 64, 0);
+            assert_eq!(header.len(), npy_header(0, columns).len());
+            assert_eq!(u16::from_le_bytes([header[8], header[9]]) as usize, header.len() - 10);
+            assert_eq!(header.last(), Some(&b'\n'));
+        }
+    }
+
+    #[test]
+    fn npy_row_count() {
+        let path = std::env::temp_dir().join(format!("dysy_export_{}.npy", std::process::id()));
+        let opts = ExportOptions { format: ExportFormat::Npy, steps: 100, ..Default::default() };
+        let rows = export_trajectory(&mut Henon::default(), &path, &opts).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rows, 100);
+        let len = 10 + u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let header = String::from_utf8_lossy(&bytes[..len]);
+        // n, x0 and x1 per row
+        assert!(header.contains("'shape': (100, 3)"), "{}", header);
+        assert_eq!(bytes.len(), len + 100 * 3 * 8);
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Serialize, Deserialize};
+
+// largest state handled by the integrators, the stages live on the stack
+pub const MAX_DIM: usize = 8;
+
+// continuous-time system dx/dt = f(t, x)
+pub trait Flow {
+    fn derivatives(&self, t: f64, x: &[f64], dx: &mut [f64]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Integrator {
+    Euler,
+    #[default]
+    Rk4,
+    // Dormand-Prince 5(4), dt is split into adaptive substeps keeping the local error within the tolerances
+    Rk45 { rtol: f64, atol: f64 },
+    // kick-drift-kick for separable Hamiltonian systems laid out as positions then velocities, falls
+    // back to Rk4 for odd dimensions
+    Leapfrog,
+}
+
+impl Integrator {
+    pub fn rk45() -> Self {
+        Integrator::Rk45 { rtol: 1e-6, atol: 1e-9 }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Integrator::Euler => "Euler",
+            Integrator::Rk4 => "RK4",
+            Integrator::Rk45 { .. } => "RK45 (Dormand-Prince)",
+            Integrator::Leapfrog => "Leapfrog",
+        }
+    }
+    // advance `x` from time t to t + dt
+    pub fn step<F: Flow + ?Sized>(&self, flow: &F, t: f64, x: &mut [f64], dt: f64) {
+        assert!(x.len() <= MAX_DIM);
+        match *self {
+            Integrator::Euler => euler(flow, t, x, dt),
+            Integrator::Rk4 => rk4(flow, t, x, dt),
+            Integrator::Rk45 { rtol, atol } => rk45(flow, t, x, dt, rtol, atol),
+            Integrator::Leapfrog if x.len().is_multiple_of(2) => leapfrog(flow, t, x, dt),
+            Integrator::Leapfrog => rk4(flow, t, x, dt),
+        }
+    }
+}
+
+fn euler<F: Flow + ?Sized>(flow: &F, t: f64, x: &mut [f64], dt: f64) {
+    let n = x.len();
+    let mut dx = [0.0; MAX_DIM];
+    flow.derivatives(t, x, &mut dx[..n]);
+    x.iter_mut().zip(dx.iter()).for_each(|(x, d)| *x += d * dt);
+}
+
+fn rk4<F: Flow + ?Sized>(flow: &F, t: f64, x: &mut [f64], dt: f64) {
+    let n = x.len();
+    let (mut k1, mut k2, mut k3, mut k4) = ([0.0; MAX_DIM], [0.0; MAX_DIM], [0.0; MAX_DIM], [0.0; MAX_DIM]);
+    let mut tmp = [0.0; MAX_DIM];
+    flow.derivatives(t, x, &mut k1[..n]);
+    for i in 0..n {tmp[i] = x[i] + k1[i] * dt * 0.5;}
+    flow.derivatives(t + dt * 0.5, &tmp[..n], &mut k2[..n]);
+    for i in 0..n {tmp[i] = x[i] + k2[i] * dt * 0.5;}
+    flow.derivatives(t + dt * 0.5, &tmp[..n], &mut k3[..n]);
+    for i in 0..n {tmp[i] = x[i] + k3[i] * dt;}
+    flow.derivatives(t + dt, &tmp[..n], &mut k4[..n]);
+    for i in 0..n {
+        x[i] += (k1[i] + k2[i] * 2.0 + k3[i] * 2.0 + k4[i]) * dt / 6.0;
+    }
+}
+
+// Dormand-Prince tableau
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [[f64; 6]; 7] = [
+    [0.0; 6],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+// 5th order weights are the last row of DP_A, these are 5th minus 4th order
+const DP_E: [f64; 7] = [
+    71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0,
+];
+
+fn rk45<F: Flow + ?Sized>(flow: &F, t: f64, x: &mut [f64], dt: f64, rtol: f64, atol: f64) {
+    let n = x.len();
+    let mut k = [[0.0; MAX_DIM]; 7];
+    let (mut tmp, mut next) = ([0.0; MAX_DIM], [0.0; MAX_DIM]);
+    let (mut done, mut h) = (0.0, dt);
+    // substeps below this are accepted whatever the error, so a stiff region cannot stall the render
+    let min_h = dt.abs() * 1e-6;
+    loop {
+        // the last substep covers exactly what is left of dt
+        let last = h.abs() >= (dt - done).abs();
+        if last {h = dt - done;}
+        for s in 0..7 {
+            for i in 0..n {
+                tmp[i] = x[i] + h * (0..s).map(|j| DP_A[s][j] * k[j][i]).sum::<f64>();
+            }
+            flow.derivatives(t + done + DP_C[s] * h, &tmp[..n], &mut k[s][..n]);
+            if s == 6 {next = tmp;}
+        }
+        // the last stage is evaluated at the 5th order solution
+        let err = (0..n)
+            .map(|i| {
+                let e = h * (0..7).map(|s| DP_E[s] * k[s][i]).sum::<f64>();
+                (e / (atol + rtol * x[i].abs().max(next[i].abs()))).abs()
+            })
+            .fold(0.0, f64::max);
+        if err <= 1.0 || h.abs() <= min_h || !err.is_finite() {
+            x.copy_from_slice(&next[..n]);
+            if last {break;}
+            done += h;
+        }
+        let factor = if err > 0.0 && err.is_finite() {(0.9 * err.powf(-0.2)).clamp(0.2, 5.0)} else {5.0};
+        h = if h.abs() * factor < min_h {min_h.copysign(dt)} else {h * factor};
+    }
+}
+
+fn leapfrog<F: Flow + ?Sized>(flow: &F, t: f64, x: &mut [f64], dt: f64) {
+    let (n, half) = (x.len(), x.len() / 2);
+    let mut dx = [0.0; MAX_DIM];
+    flow.derivatives(t, x, &mut dx[..n]);
+    for i in half..n {x[i] += dx[i] * dt * 0.5;}
+    flow.derivatives(t + dt * 0.5, x, &mut dx[..n]);
+    for i in 0..half {x[i] += dx[i] * dt;}
+    flow.derivatives(t + dt, x, &mut dx[..n]);
+    for i in half..n {x[i] += dx[i] * dt * 0.5;}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // x'' = -x with x(0) = 1, v(0) = 0, laid out as position then velocity. counts the evaluations
+    #[derive(Default)]
+    struct Oscillator {
+        evals: Cell<usize>,
+    }
+
+    impl Flow for Oscillator {
+        fn derivatives(&self, _t: f64, x: &[f64], dx: &mut [f64]) {
+            self.evals.set(self.evals.get() + 1);
+            dx[0] = x[1];
+            dx[1] = -x[0];
+        }
+    }
+
+    // error at t = 1 with `steps` steps
+    fn error(integrator: Integrator, steps: usize) -> f64 {
+        let (flow, dt) = (Oscillator::default(), 1.0 / steps as f64);
+        let mut x = [1.0, 0.0];
+        for i in 0..steps {
+            integrator.step(&flow, i as f64 * dt, &mut x, dt);
+        }
+        ((x[0] - 1.0f64.cos()).powi(2) + (x[1] + 1.0f64.sin()).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn order() {
+        for (integrator, order) in [(Integrator::Euler, 1.0), (Integrator::Rk4, 4.0), (Integrator::Leapfrog, 2.0)] {
+            let measured = (error(integrator, 50) / error(integrator, 100)).log2();
+            assert!((measured - order).abs() < 0.2, "{}: {}", integrator.name(), measured);
+        }
+    }
+
+    #[test]
+    fn rk45_adapts_substeps() {
+        let run = |rtol: f64, atol: f64| {
+            let flow = Oscillator::default();
+            let mut x = [1.0, 0.0];
+            // one step much longer than the oscillator allows without substeps
+            Integrator::Rk45 { rtol, atol }.step(&flow, 0.0, &mut x, 5.0);
+            let err = ((x[0] - 5.0f64.cos()).powi(2) + (x[1] + 5.0f64.sin()).powi(2)).sqrt();
+            (err, flow.evals.get())
+        };
+        let (loose, loose_evals) = run(1e-3, 1e-6);
+        let (tight, tight_evals) = run(1e-10, 1e-12);
+        assert!(tight < 1e-8, "{}", tight);
+        assert!(tight < loose, "{} {}", tight, loose);
+        assert!(tight_evals > loose_evals, "{} {}", tight_evals, loose_evals);
+    }
+}
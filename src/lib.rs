@@ -1,6 +1,35 @@
+//! dysy: attractor generator for dynamical systems.
+//!
+//! The engine (`attractors`, `state`, `util`) has no GUI dependencies.
+//! The egui application `MyApp` is only built with the `gui` feature (enabled by default).
+
+#[cfg(feature = "gui")]
 mod app;
+#[cfg(feature = "gui")]
 pub use app::MyApp;
 
-mod attractors;
-mod state;
-mod util;
\ No newline at end of file
+pub mod attractors;
+pub use attractors::Attractor;
+
+pub mod state;
+pub use state::State;
+
+pub mod util;
+pub use util::Palette;
+
+pub mod integrator;
+pub use integrator::Integrator;
+
+pub mod camera;
+pub use camera::Camera;
+
+pub mod render;
+
+pub mod analysis;
+
+pub mod export;
+
+pub mod animation;
+
+pub mod params;
+pub mod cli;
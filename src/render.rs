@@ -0,0 +1,411 @@
+use image::{RgbImage, Rgb, DynamicImage};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::attractors::Attractor;
+use crate::util::Palette;
+
+pub mod tiled;
+pub mod bifurcation;
+pub mod poincare;
+pub mod basin;
+
+// minimum iterations per seed, so that the warm-up of each seed stays negligible
+const SEED_ITERS: usize = 100000;
+// relative size of the perturbation of the initial values of each seed
+const SEED_PERTURBATION: f64 = 1e-4;
+// number of partial results reported by gen_hist_progressive
+const PASSES: usize = 20;
+
+// bounding box of projected points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub top: f64,
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self {
+            top: f64::MAX,
+            left: f64::MAX,
+            bottom: f64::MIN,
+            right: f64::MIN,
+        }
+    }
+}
+
+impl Bounds {
+    pub fn from_points(points: impl Iterator<Item = (f64, f64)>) -> Self {
+        let mut bounds = Self::default();
+        for (x, y) in points {
+            bounds.add(x, y);
+        }
+        bounds
+    }
+    pub fn add(&mut self, x: f64, y: f64) {
+        if !x.is_finite() || !y.is_finite() {return;}
+        self.top = self.top.min(y);
+        self.left = self.left.min(x);
+        self.bottom = self.bottom.max(y);
+        self.right = self.right.max(x);
+    }
+    // smallest box containing both
+    pub fn union(&self, other: &Bounds) -> Self {
+        Self {
+            top: self.top.min(other.top),
+            left: self.left.min(other.left),
+            bottom: self.bottom.max(other.bottom),
+            right: self.right.max(other.right),
+        }
+    }
+}
+
+// maps projected points to the pixels of a w x h image, keeping the aspect ratio
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    wc: f64,
+    hc: f64,
+    m: f64,
+    w: usize,
+    h: usize,
+}
+
+impl Viewport {
+    pub fn new(bounds: &Bounds, w: usize, h: usize) -> Self {
+        Self {
+            wc: (bounds.right + bounds.left) * 0.5,
+            hc: (bounds.bottom + bounds.top) * 0.5,
+            m: (w as f64 / (bounds.right - bounds.left)).min(h as f64 / (bounds.bottom - bounds.top)),
+            w,
+            h,
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.w
+    }
+    pub fn height(&self) -> usize {
+        self.h
+    }
+    // pixel coordinate, points outside the image are clamped to the edges
+    pub fn pixel(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        if !x.is_finite() || !y.is_finite() {return None;}
+        let (iw, ih) = (self.w as i64, self.h as i64);
+        let tw = (((x - self.wc) * self.m).round() as i64 + iw/2).clamp(0, iw-1) as usize;
+        let th = (((y - self.hc) * self.m).round() as i64 + ih/2).clamp(0, ih-1) as usize;
+        Some((tw, th))
+    }
+}
+
+// projected trajectory from the initial values, the first `skip` of `n` iterations are discarded
+pub struct Trajectory<'a, A: Attractor + ?Sized> {
+    at: &'a mut A,
+    remaining: usize,
+}
+
+impl<'a, A: Attractor + ?Sized> Trajectory<'a, A> {
+    pub fn new(at: &'a mut A, n: usize, skip: usize) -> Self {
+        at.state_mut().set_init();
+        for _ in 0..skip.min(n) {
+            at.apply_map_func();
+        }
+        Self {
+            at,
+            remaining: n.saturating_sub(skip),
+        }
+    }
+}
+
+impl<A: Attractor + ?Sized> Iterator for Trajectory<'_, A> {
+    type Item = (f64, f64);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {return None;}
+        self.remaining -= 1;
+        self.at.apply_map_func();
+        Some(self.at.view())
+    }
+}
+
+pub fn search_edges<A: Attractor + ?Sized>(at: &mut A, n: usize, skip: usize) -> Bounds {
+    let bounds = Bounds::from_points(Trajectory::new(at, n, skip));
+    at.state_mut().set_init();
+    bounds
+}
+
+fn seed_count(n: usize) -> usize {
+    (rayon::current_num_threads() * 4).min(n / SEED_ITERS).max(1)
+}
+
+// 3D bounding box of the trajectory as its lowest and highest corner
+fn search_box<A: Attractor + ?Sized>(at: &mut A, n: usize, skip: usize) -> ([f64; 3], [f64; 3]) {
+    let (mut lo, mut hi) = ([f64::MAX; 3], [f64::MIN; 3]);
+    at.state_mut().set_init();
+    for i in 0..n {
+        at.apply_map_func();
+        if i < skip {continue;}
+        let (x, y, z) = at.state().get_xyz();
+        if !x.is_finite() || !y.is_finite() || !z.is_finite() {continue;}
+        for (k, v) in [x, y, z].into_iter().enumerate() {
+            lo[k] = lo[k].min(v);
+            hi[k] = hi[k].max(v);
+        }
+    }
+    at.state_mut().set_init();
+    (lo, hi)
+}
+
+// center the camera on a 3D bounding box, the image shows a square of the camera extent whatever
+// the angles
+fn center_camera<A: Attractor + ?Sized>(at: &mut A, lo: [f64; 3], hi: [f64; 3]) -> Bounds {
+    let center = [0, 1, 2].map(|k| if lo[k] <= hi[k] {(lo[k] + hi[k]) * 0.5} else {0.0});
+    let radius = (0..3).map(|k| (hi[k] - lo[k]).max(0.0).powi(2)).sum::<f64>().sqrt() * 0.5;
+    let Some(camera) = at.state_mut().camera_mut() else {return Bounds::default();};
+    camera.set_pivot(center, radius);
+    let extent = camera.extent();
+    Bounds { top: -extent, left: -extent, bottom: extent, right: extent }
+}
+
+fn fit_camera<A: Attractor + ?Sized>(at: &mut A, n: usize, skip: usize) -> Bounds {
+    let (lo, hi) = search_box(at, n, skip);
+    center_camera(at, lo, hi)
+}
+
+// prepare the system and fit the bounding box of `n` iterations into a w x h image,
+// None if the system cannot be iterated
+pub fn fit_viewport<A: Attractor + ?Sized>(at: &mut A, n: usize, w: usize, h: usize) -> Option<Viewport> {
+    if !at.prepare() {return None;}
+    let skip = at.skip();
+    let search_iters = at.search_iters(n);
+    let bounds = if at.state().camera().is_some() {
+        fit_camera(at, search_iters, skip)
+    }
+    else {
+        search_edges(at, search_iters, skip)
+    };
+    Some(Viewport::new(&bounds, w, h))
+}
+
+// one viewport holding `sets` parameter sets of `at`, e.g. the keyframes of an animation, where
+// `apply(at, i)` switches to set i. None if none of the sets can be iterated
+pub fn fit_viewport_all<A: Attractor + ?Sized>(
+    at: &mut A, sets: usize, n: usize, w: usize, h: usize, mut apply: impl FnMut(&mut A, usize)
+) -> Option<Viewport> {
+    let mut bounds = Bounds::default();
+    let (mut lo, mut hi) = ([f64::MAX; 3], [f64::MIN; 3]);
+    let mut fitted = false;
+    for i in 0..sets {
+        apply(at, i);
+        if !at.prepare() {continue;}
+        fitted = true;
+        let (skip, search_iters) = (at.skip(), at.search_iters(n));
+        if at.state().camera().is_some() {
+            let (l, u) = search_box(at, search_iters, skip);
+            for k in 0..3 {
+                lo[k] = lo[k].min(l[k]);
+                hi[k] = hi[k].max(u[k]);
+            }
+        }
+        else {
+            bounds = bounds.union(&search_edges(at, search_iters, skip));
+        }
+    }
+    if !fitted {return None;}
+    if at.state().camera().is_some() {
+        bounds = center_camera(at, lo, hi);
+    }
+    Some(Viewport::new(&bounds, w, h))
+}
+
+// histogram of one orbit, or for ergodic systems of independently warmed-up seeds near the
+// initial values iterated in parallel (the first seed starts exactly at the initial values)
+pub struct Accumulator {
+    orbits: Vec<Box<dyn Attractor>>,
+    viewport: Viewport,
+    rows: Range<usize>,
+    counts: Vec<u32>,
+    done: usize,
+}
+
+impl Accumulator {
+    // search the bounding box for `n` iterations and warm up the orbits, None if the system cannot be iterated
+    pub fn new<A: Attractor + ?Sized>(at: &mut A, n: usize, w: usize, h: usize) -> Option<Self> {
+        let viewport = fit_viewport(at, n, w, h)?;
+        Some(Self::with_rows(at, viewport, 0..h, n))
+    }
+    // accumulate only `rows` of the image, `at` must be prepared
+    pub fn with_rows<A: Attractor + ?Sized>(at: &A, viewport: Viewport, rows: Range<usize>, n: usize) -> Self {
+        let skip = at.skip();
+        let seeds = if at.ergodic() {seed_count(n)} else {1};
+        let orbits = (0..seeds)
+            .map(|i| {
+                let mut seed = at.clone_box();
+                if i > 0 {
+                    let mut rng = StdRng::seed_from_u64(i as u64);
+                    let x_range = seed.state().get_x_range();
+                    let scale = (x_range.end() - x_range.start()) * SEED_PERTURBATION;
+                    seed.state_mut().get_init_val_mut().iter_mut()
+                        .for_each(|x| *x += rng.gen_range(-scale..=scale));
+                }
+                seed.state_mut().set_init();
+                for _ in 0..skip {
+                    seed.apply_map_func();
+                }
+                seed
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            orbits,
+            counts: vec![0; viewport.width() * rows.len()],
+            viewport,
+            rows,
+            done: 0,
+        }
+    }
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+    // number of iterations accumulated so far
+    pub fn done(&self) -> usize {
+        self.done
+    }
+    // continue the orbits for `n` more iterations in total. every worker counts into its own
+    // histogram, they are summed at the end
+    pub fn advance(&mut self, n: usize) {
+        let seeds = self.orbits.len();
+        let (viewport, rows) = (&self.viewport, &self.rows);
+        let w = viewport.width();
+        let len = self.counts.len();
+        // about one histogram per thread
+        let min_len = seeds.div_ceil(rayon::current_num_threads());
+        let counts = self.orbits.par_iter_mut().enumerate().with_min_len(min_len)
+            .fold(|| vec![0u32; len], |mut counts, (i, orbit)| {
+                for _ in 0..n / seeds + usize::from(i < n % seeds) {
+                    orbit.apply_map_func();
+                    let (x, y) = orbit.view();
+                    if let Some((tw, th)) = viewport.pixel(x, y) {
+                        if rows.contains(&th) {
+                            let c = &mut counts[(th - rows.start) * w + tw];
+                            *c = c.saturating_add(1);
+                        }
+                    }
+                }
+                counts
+            })
+            .reduce_with(|mut a, b| {
+                a.iter_mut().zip(b.iter()).for_each(|(a, b)| *a = a.saturating_add(*b));
+                a
+            });
+        if let Some(counts) = counts {
+            // a single hot pixel can take more than u32::MAX of a long render
+            self.counts.iter_mut().zip(counts.iter()).for_each(|(a, b)| *a = a.saturating_add(*b));
+        }
+        self.done += n;
+    }
+    // raw counts of the accumulated rows
+    pub fn counts(&self) -> Vec<u32> {
+        self.counts.clone()
+    }
+    // normalized histogram of the iterations so far
+    pub fn hist(&self) -> Vec<f64> {
+        let mut hist = self.counts.iter().map(|c| *c as f64).collect::<Vec<_>>();
+        normalize(&mut hist);
+        hist
+    }
+}
+
+// shared between a render running on another thread and its owner
+#[derive(Debug, Default)]
+pub struct Progress {
+    pub(crate) done: AtomicUsize,
+    pub(crate) total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {return 0.0;}
+        self.done.load(Ordering::Relaxed) as f32 / total as f32
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// scale the histogram into 0.0..=1.0
+pub fn normalize(hist: &mut [f64]) {
+    let mx_its = hist.iter().cloned().fold(0.0f64, f64::max);
+    if mx_its > 0.0 {
+        let inv_mx_its = 1.0 / mx_its;
+        hist.iter_mut().for_each(|v| *v *= inv_mx_its);
+    }
+}
+
+// normalized density of the projected trajectory
+pub fn gen_hist<A: Attractor + ?Sized>(at: &mut A, n: usize, w: usize, h: usize) -> Vec<f64> {
+    match Accumulator::new(at, n, w, h) {
+        Some(mut acc) => {
+            acc.advance(n);
+            acc.hist()
+        },
+        None => vec![0.0; w * h],
+    }
+}
+
+// gen_hist into a given viewport, e.g. one shared by the frames of an animation
+pub fn gen_hist_in<A: Attractor + ?Sized>(at: &mut A, viewport: Viewport, n: usize) -> Vec<f64> {
+    let (w, h) = (viewport.width(), viewport.height());
+    if !at.prepare() {return vec![0.0; w * h];}
+    let mut acc = Accumulator::with_rows(at, viewport, 0..h, n);
+    acc.advance(n);
+    acc.hist()
+}
+
+// gen_hist in passes, `partial` receives the histogram and the iterations done after every pass
+// but the last, returns None when cancelled
+pub fn gen_hist_progressive<A: Attractor + ?Sized>(
+    at: &mut A, n: usize, w: usize, h: usize, progress: &Progress, mut partial: impl FnMut(&[f64], usize)
+) -> Option<Vec<f64>> {
+    progress.total.store(n, Ordering::Relaxed);
+    let Some(mut acc) = Accumulator::new(at, n, w, h) else {
+        return Some(vec![0.0; w * h]);
+    };
+    let pass = n.div_ceil(PASSES).max(1);
+    while acc.done() < n {
+        if progress.is_cancelled() {return None;}
+        acc.advance(pass.min(n - acc.done()));
+        progress.done.store(acc.done(), Ordering::Relaxed);
+        if acc.done() < n {
+            partial(&acc.hist(), acc.done());
+        }
+    }
+    Some(acc.hist())
+}
+
+// iteration count giving the same density as `n` iterations at 1024x1024
+pub fn scaled_iters(n: usize, w: usize, h: usize) -> usize {
+    ((n as f64) * ((w * h) as f64) / (1024.0 * 1024.0)).round().max(1.0) as usize
+}
+
+// brightness factor of an image of `n` iterations
+pub fn color_factor(n: usize, w: usize, h: usize) -> f64 {
+    (10_000_000.0 / (n as f64)).sqrt() * ((w * h) as f64) / (1024.0 * 1024.0) * 100.
+}
+
+pub fn colorize(img_vec: &[f64], n: usize, w: usize, h: usize, plt: &Palette) -> DynamicImage {
+    let factor = color_factor(n, w, h);
+    let img = RgbImage::from_par_fn(w as u32, h as u32, |x, y| {
+        let v = img_vec[(y as usize) * w + (x as usize)];
+        let (r, g, b) = plt.get_col(v, v, factor);
+        Rgb([r, g, b])
+    });
+
+    DynamicImage::ImageRgb8(img)
+}
@@ -0,0 +1,177 @@
+use image::{RgbImage, Rgb, DynamicImage};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+
+use super::Progress;
+use crate::attractors::Attractor;
+use crate::util::Palette;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BasinMode {
+    // iterations until the orbit leaves the escape radius
+    EscapeTime,
+    // which attractor the orbit ends on, told apart by the centroid of its last iterations
+    Attractor,
+    // iterations until the orbit comes to rest
+    SettleTime,
+    // iterations until one of the swept components, an angle, flips over -PI..PI
+    FlipTime,
+}
+
+impl BasinMode {
+    pub const ALL: [BasinMode; 4] = [BasinMode::EscapeTime, BasinMode::Attractor, BasinMode::SettleTime, BasinMode::FlipTime];
+    pub fn name(&self) -> &'static str {
+        match self {
+            BasinMode::EscapeTime => "escape time",
+            BasinMode::Attractor => "attractor",
+            BasinMode::SettleTime => "settle time",
+            BasinMode::FlipTime => "flip time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasinOptions {
+    pub mode: BasinMode,
+    // initial value components swept along the horizontal and vertical axis
+    pub axes: [usize; 2],
+    pub ranges: [RangeInclusive<f64>; 2],
+    pub max_iters: usize,
+    pub escape: f64,
+    // change per iteration, or per unit time for flows, under which the orbit is at rest
+    pub tolerance: f64,
+}
+
+impl BasinOptions {
+    // the first two components over the range of the state, flip time of the angles for systems of angles
+    pub fn new<A: Attractor + ?Sized>(at: &A) -> Self {
+        let x_range = at.state().get_x_range();
+        let width = x_range.end() - x_range.start();
+        let max_iters = match at.state().get_dt() {
+            Some(dt) => ((10.0 / dt) as usize).clamp(100, 100000),
+            None => 200,
+        };
+        let angles = at.angles();
+        let (mode, axes, ranges) = match angles.as_slice() {
+            [a, b, ..] => (BasinMode::FlipTime, [*a, *b], [-PI..=PI, -PI..=PI]),
+            _ if at.state().get_dt().is_some() => (BasinMode::Attractor, [0, 1], [x_range.clone(), x_range.clone()]),
+            _ => (BasinMode::EscapeTime, [0, 1], [x_range.clone(), x_range.clone()]),
+        };
+        Self {
+            mode,
+            axes,
+            ranges,
+            max_iters,
+            escape: 10.0 * x_range.start().abs().max(x_range.end().abs()).max(1.0),
+            tolerance: 1e-6 * width.max(1e-12),
+        }
+    }
+}
+
+// iterations, or time units for flows, averaged into the centroid of BasinMode::Attractor
+const WINDOW: usize = 64;
+const WINDOW_TIME: f64 = 5.0;
+// centroids closer than this fraction of the range of the state belong to the same attractor
+const CLUSTER: f64 = 0.02;
+
+// time of the event, the centroid of the final iterations for BasinMode::Attractor, None if it never happens
+fn classify<A: Attractor + ?Sized>(at: &mut A, opts: &BasinOptions) -> Option<Vec<f64>> {
+    at.state_mut().set_init();
+    let time = |at: &A, i: usize| if at.state().get_dt().is_some() {at.state().time} else {i as f64};
+    let dt = at.state().get_dt().unwrap_or(1.0);
+    let window = match at.state().get_dt() {
+        Some(dt) => ((WINDOW_TIME / dt) as usize).max(WINDOW),
+        None => WINDOW,
+    }.min(opts.max_iters);
+    let mut centroid = vec![0.0; at.state().get_xs().len()];
+    let mut prev = at.state().get_xs().to_vec();
+    for i in 1..=opts.max_iters {
+        at.apply_map_func();
+        let x = at.state().get_xs();
+        let escaped = x.iter().any(|v| !v.is_finite() || v.abs() > opts.escape);
+        match opts.mode {
+            BasinMode::EscapeTime if escaped => return Some(vec![time(at, i)]),
+            BasinMode::FlipTime if opts.axes.iter().any(|&k| x[k].abs() > PI) => return Some(vec![time(at, i)]),
+            BasinMode::SettleTime | BasinMode::Attractor if escaped => return None,
+            BasinMode::SettleTime => {
+                let d = x.iter().zip(prev.iter()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+                if d < opts.tolerance * dt {return Some(vec![time(at, i)]);}
+                prev.copy_from_slice(x);
+            },
+            BasinMode::Attractor if i + window > opts.max_iters => {
+                centroid.iter_mut().zip(x.iter()).for_each(|(c, v)| *c += v / window as f64);
+            },
+            _ => {},
+        }
+    }
+    (opts.mode == BasinMode::Attractor).then_some(centroid)
+}
+
+// one value per pixel in 0.0..=1.0, NaN where nothing happened within max_iters.
+// times are log scaled, attractors are numbered in order of discovery. None when cancelled
+pub fn gen_basin<A: Attractor + ?Sized>(
+    at: &A, opts: &BasinOptions, w: usize, h: usize, progress: &Progress
+) -> Option<Vec<f64>> {
+    let dim = at.state().get_xs().len();
+    let axes = opts.axes.map(|k| k.min(dim - 1));
+    let opts = BasinOptions { axes, ..opts.clone() };
+    let coord = |r: &RangeInclusive<f64>, i: usize, n: usize| r.start() + (r.end() - r.start()) * (i as f64 + 0.5) / n as f64;
+    progress.total.store(h, Ordering::Relaxed);
+    progress.done.store(0, Ordering::Relaxed);
+
+    // every worker iterates its own copy of the system
+    let template = Mutex::new(at.clone_box());
+    let results = (0..h).into_par_iter()
+        .map_init(|| template.lock().unwrap().clone_box(), |row, y| {
+            if !row.prepare() {return Some(vec![None; w]);}
+            let values = (0..w)
+                .map(|x| {
+                    if progress.is_cancelled() {return None;}
+                    let init = row.state_mut().get_init_val_mut();
+                    init[axes[0]] = coord(&opts.ranges[0], x, w);
+                    // larger values at the top
+                    init[axes[1]] = coord(&opts.ranges[1], h - 1 - y, h);
+                    Some(classify(row.as_mut(), &opts))
+                })
+                .collect::<Option<Vec<_>>>();
+            progress.done.fetch_add(1, Ordering::Relaxed);
+            values
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    if opts.mode == BasinMode::Attractor {
+        // number the attractors by their centroids
+        let x_range = at.state().get_x_range();
+        let radius = (x_range.end() - x_range.start()) * CLUSTER;
+        let mut centroids: Vec<Vec<f64>> = vec![];
+        let labels = results.iter().flatten()
+            .map(|c| c.as_ref().map(|c| {
+                let near = |p: &Vec<f64>| p.iter().zip(c.iter()).all(|(a, b)| (a - b).abs() < radius);
+                centroids.iter().position(near).unwrap_or_else(|| {
+                    centroids.push(c.clone());
+                    centroids.len() - 1
+                })
+            }))
+            .collect::<Vec<_>>();
+        let n = centroids.len() as f64;
+        return Some(labels.into_iter().map(|l| l.map_or(f64::NAN, |l| (l as f64 + 1.0) / n)).collect());
+    }
+    let times = results.into_iter().flatten().map(|t| t.map_or(f64::NAN, |t| t[0])).collect::<Vec<_>>();
+    let max = times.iter().cloned().filter(|t| t.is_finite()).fold(0.0, f64::max);
+    Some(times.into_iter().map(|t| if max > 0.0 {(1.0 + t).ln() / (1.0 + max).ln()} else {t}).collect())
+}
+
+// flat colors of the palette, NaN is black
+pub fn colorize_levels(values: &[f64], w: usize, h: usize, plt: &Palette) -> DynamicImage {
+    let factor = 255.0 / plt.brightness(1.0);
+    let img = RgbImage::from_par_fn(w as u32, h as u32, |x, y| {
+        let v = values[(y as usize) * w + (x as usize)];
+        if v.is_nan() {return Rgb([0, 0, 0]);}
+        let (r, g, b) = plt.get_col(v, 1.0, factor);
+        Rgb([r, g, b])
+    });
+    DynamicImage::ImageRgb8(img)
+}
@@ -0,0 +1,98 @@
+use rayon::prelude::*;
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+
+use super::{normalize, Progress};
+use crate::analysis::dimension::default_stride;
+use crate::attractors::Attractor;
+
+// samples beyond this magnitude are treated as an escaping orbit
+const ESCAPE: f64 = 1e6;
+
+#[derive(Debug, Clone)]
+pub struct BifurcationOptions {
+    // index into coefs() swept along the horizontal axis
+    pub coef: usize,
+    pub range: RangeInclusive<f64>,
+    // index into State::get_xs() plotted along the vertical axis
+    pub component: usize,
+    pub transient: usize,
+    pub samples: usize,
+    // iterations between samples
+    pub stride: usize,
+}
+
+impl BifurcationOptions {
+    // sweep the whole range of `coef`, flows run 100 time units of transient and are sampled every 0.1.
+    // None when the system has no such coefficient
+    pub fn new<A: Attractor + ?Sized>(at: &A, coef: usize) -> Option<Self> {
+        let stride = default_stride(at);
+        Some(Self {
+            coef,
+            range: at.coef_ranges().get(coef)?.clone(),
+            component: 0,
+            transient: if at.state().get_dt().is_some() {stride * 1000} else {1000},
+            samples: 1000,
+            stride,
+        })
+    }
+}
+
+// sampled values of the component for every column, empty where the orbit escapes. None when
+// cancelled, every worker iterates its own copy of the system
+fn sweep<A: Attractor + ?Sized>(at: &A, opts: &BifurcationOptions, w: usize, progress: &Progress) -> Option<Vec<Vec<f64>>> {
+    let (start, end) = (*opts.range.start(), *opts.range.end());
+    progress.total.store(w, Ordering::Relaxed);
+    progress.done.store(0, Ordering::Relaxed);
+
+    let template = Mutex::new(at.clone_box());
+    (0..w).into_par_iter()
+        .map_init(|| template.lock().unwrap().clone_box(), |column, i| {
+            if progress.is_cancelled() {return None;}
+            column.coefs_mut()[opts.coef] = start + (end - start) * i as f64 / (w.max(2) - 1) as f64;
+            let values = sample(column.as_mut(), opts);
+            progress.done.fetch_add(1, Ordering::Relaxed);
+            Some(values)
+        })
+        .collect()
+}
+
+fn sample(column: &mut dyn Attractor, opts: &BifurcationOptions) -> Vec<f64> {
+    if !column.prepare() {return vec![];}
+    column.state_mut().set_init();
+    for _ in 0..opts.transient {
+        column.apply_map_func();
+    }
+    let mut values = Vec::with_capacity(opts.samples);
+    for _ in 0..opts.samples {
+        for _ in 0..opts.stride.max(1) {
+            column.apply_map_func();
+        }
+        let v = column.state().get_xs()[opts.component];
+        if !v.is_finite() || v.abs() > ESCAPE {return vec![];}
+        values.push(v);
+    }
+    values
+}
+
+// normalized density of the component over the swept coefficient, larger values at the top.
+// the vertical range fits the samples of all columns. None when cancelled
+pub fn gen_bifurcation<A: Attractor + ?Sized>(
+    at: &A, opts: &BifurcationOptions, w: usize, h: usize, progress: &Progress
+) -> Option<Vec<f64>> {
+    let columns = sweep(at, opts, w, progress)?;
+    let (lo, hi) = columns.iter().flatten()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let mut hist = vec![0.0; w * h];
+    if lo > hi {return Some(hist);}
+    let scale = if hi > lo {(h - 1) as f64 / (hi - lo)} else {0.0};
+    for (x, values) in columns.iter().enumerate() {
+        for v in values {
+            let y = h - 1 - ((v - lo) * scale).round() as usize;
+            hist[y * w + x] += 1.0;
+        }
+    }
+    normalize(&mut hist);
+    Some(hist)
+}
@@ -0,0 +1,133 @@
+use std::f64::consts::PI;
+use std::sync::atomic::Ordering;
+
+use super::{Bounds, Progress, Viewport, normalize};
+use crate::attractors::Attractor;
+
+// iterations between checks for cancellation
+const CHECK_ITERS: usize = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Section {
+    // hyperplane normal . x = offset, crossed in the direction of the normal
+    Plane { normal: Vec<f64>, offset: f64 },
+    // state at every multiple of the period
+    Stroboscopic { period: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoincareOptions {
+    pub section: Section,
+    // state components drawn as the horizontal and vertical axis
+    pub axes: [usize; 2],
+    pub transient: usize,
+    pub points: usize,
+    // the search gives up after this many iterations if the orbit rarely crosses the section
+    pub max_iters: usize,
+}
+
+impl PoincareOptions {
+    // stroboscopic for forced systems, otherwise the plane through the mean of the last component,
+    // drawing the first two other components. None when cancelled
+    pub fn new<A: Attractor + ?Sized>(at: &mut A, progress: &Progress) -> Option<Self> {
+        let dim = at.state().get_xs().len();
+        let dt = at.state().get_dt().unwrap_or(1.0);
+        let transient = ((100.0 / dt) as usize).min(1000000);
+        let (section, axes) = match at.forcing_period() {
+            Some(period) => (Section::Stroboscopic { period }, [0, 1.min(dim - 1)]),
+            None => {
+                let k = dim - 1;
+                let n = at.search_iters(0);
+                progress.total.store(n, Ordering::Relaxed);
+                at.state_mut().set_init();
+                let mut sum = 0.0;
+                for i in 0..n {
+                    if i % CHECK_ITERS == 0 {
+                        if progress.is_cancelled() {return None;}
+                        progress.done.store(i, Ordering::Relaxed);
+                    }
+                    at.apply_map_func();
+                    sum += at.state().get_xs()[k];
+                }
+                at.state_mut().set_init();
+                let mut normal = vec![0.0; dim];
+                normal[k] = 1.0;
+                let offset = if (sum / n as f64).is_finite() {sum / n as f64} else {0.0};
+                let axes = if k >= 2 {[0, 1]} else {[0, 0]};
+                (Section::Plane { normal, offset }, axes)
+            },
+        };
+        Some(Self {
+            section,
+            axes,
+            transient,
+            points: 50000,
+            max_iters: 500000000,
+        })
+    }
+}
+
+// section points projected on the axes, None when cancelled
+pub fn section_points<A: Attractor + ?Sized>(
+    at: &mut A, opts: &PoincareOptions, progress: &Progress
+) -> Option<Vec<(f64, f64)>> {
+    let dim = at.state().get_xs().len();
+    let [a, b] = opts.axes.map(|i| i.min(dim - 1));
+    let angles = at.angles();
+    let side = |x: &[f64]| match &opts.section {
+        Section::Plane { normal, offset } => x.iter().zip(normal).map(|(x, n)| x * n).sum::<f64>() - offset,
+        Section::Stroboscopic { .. } => 0.0,
+    };
+
+    progress.total.store(opts.points, Ordering::Relaxed);
+    at.state_mut().set_init();
+    for _ in 0..opts.transient {
+        at.apply_map_func();
+    }
+    let mut prev = at.state().get_xs().to_vec();
+    let (mut prev_side, mut prev_time) = (side(&prev), at.state().time);
+    let mut points = Vec::with_capacity(opts.points);
+    for i in 0..opts.max_iters {
+        if points.len() >= opts.points {break;}
+        if i % CHECK_ITERS == 0 && progress.is_cancelled() {return None;}
+        at.apply_map_func();
+        let (x, time) = (at.state().get_xs(), at.state().time);
+        if x.iter().any(|v| !v.is_finite()) {break;}
+        // the fraction of the step where the section is crossed
+        let s = side(x);
+        let crossing = match &opts.section {
+            Section::Plane { .. } => (prev_side < 0.0 && s >= 0.0).then(|| prev_side / (prev_side - s)),
+            Section::Stroboscopic { period } => {
+                let k = (time / period).floor();
+                (k > (prev_time / period).floor()).then(|| (k * period - prev_time) / (time - prev_time))
+            },
+        };
+        // a jump of more than half a turn in an angle is a wrap around, not a crossing
+        let wrapped = angles.iter().any(|&k| (x[k] - prev[k]).abs() > PI);
+        if let (Some(f), false) = (crossing, wrapped) {
+            points.push((prev[a] + (x[a] - prev[a]) * f, prev[b] + (x[b] - prev[b]) * f));
+            progress.done.store(points.len(), Ordering::Relaxed);
+        }
+        prev.copy_from_slice(x);
+        (prev_side, prev_time) = (s, time);
+    }
+    at.state_mut().set_init();
+    Some(points)
+}
+
+// normalized density of the section points fitted into a w x h image, None when cancelled
+pub fn gen_section<A: Attractor + ?Sized>(
+    at: &mut A, opts: &PoincareOptions, w: usize, h: usize, progress: &Progress
+) -> Option<Vec<f64>> {
+    let mut hist = vec![0.0; w * h];
+    if !at.prepare() {return Some(hist);}
+    let points = section_points(at, opts, progress)?;
+    let viewport = Viewport::new(&Bounds::from_points(points.iter().cloned()), w, h);
+    for (x, y) in points {
+        if let Some((tw, th)) = viewport.pixel(x, y) {
+            hist[th * w + tw] += 1.0;
+        }
+    }
+    normalize(&mut hist);
+    Some(hist)
+}
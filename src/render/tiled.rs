@@ -0,0 +1,97 @@
+use rayon::prelude::*;
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+use super::{Accumulator, Progress, fit_viewport, color_factor};
+use crate::attractors::Attractor;
+use crate::util::Palette;
+use crate::params;
+
+// pixels per tile when the tile height is not given, 256 MB of counters
+const TILE_PIXELS: usize = 1 << 26;
+
+pub fn default_tile_rows(w: usize) -> usize {
+    (TILE_PIXELS / w).max(1)
+}
+
+// removes the spilled counters even if rendering fails
+struct TileFile(PathBuf);
+
+impl Drop for TileFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// render a w x h image in horizontal tiles of `tile_rows` rows and stream it to a PNG file.
+// every tile replays the same `n` iterations with the same bounding box but only counts its own rows,
+// so the render costs tiles x n iterations. the counts are spilled next to the output as u32, saturating
+// on hot pixels, so memory stays at one tile.
+#[allow(clippy::too_many_arguments)]
+pub fn save_tiled<A: Attractor + ?Sized>(
+    at: &mut A, path: &Path, n: usize, w: usize, h: usize, tile_rows: usize, plt: &Palette, progress: &Progress
+) -> Result<()> {
+    let viewport = fit_viewport(at, n, w, h)
+        .ok_or_else(|| anyhow!("{} cannot be iterated", at.name()))?;
+    let tile_rows = tile_rows.clamp(1, h);
+    let tiles = (0..h).step_by(tile_rows).map(|y| y..(y + tile_rows).min(h)).collect::<Vec<_>>();
+    progress.total.store(tiles.len() * 2, Ordering::Relaxed);
+
+    // accumulate every tile and spill the counts
+    let tile_file = TileFile(path.with_extension("tiles.tmp"));
+    let mut writer = BufWriter::new(fs::File::create(&tile_file.0)?);
+    let mut mx_its = 0;
+    for (i, rows) in tiles.iter().enumerate() {
+        if progress.is_cancelled() {return Err(anyhow!("cancelled"));}
+        let mut acc = Accumulator::with_rows(at, viewport, rows.clone(), n);
+        acc.advance(n);
+        let counts = acc.counts();
+        mx_its = mx_its.max(counts.iter().cloned().max().unwrap_or(0));
+        for c in counts {
+            writer.write_all(&c.to_le_bytes())?;
+        }
+        progress.done.store(i + 1, Ordering::Relaxed);
+    }
+    writer.flush()?;
+    drop(writer);
+
+    // colorize the tiles with the global maximum and stream the rows, a partial image is removed
+    let write_png = || -> Result<()> {
+        let file = BufWriter::new(fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, w as u32, h as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        params::add_png_text(&mut encoder, at, plt, Some(n), w, h)?;
+        let mut png_writer = encoder.write_header()?;
+        let mut stream = png_writer.stream_writer()?;
+
+        let mut reader = BufReader::new(fs::File::open(&tile_file.0)?);
+        let inv_mx_its = if mx_its > 0 {1.0 / mx_its as f64} else {0.0};
+        let factor = color_factor(n, w, h);
+        let mut bytes = vec![];
+        let mut rgb = vec![];
+        for (i, rows) in tiles.iter().enumerate() {
+            if progress.is_cancelled() {return Err(anyhow!("cancelled"));}
+            bytes.resize(rows.len() * w * 4, 0);
+            reader.read_exact(&mut bytes)?;
+            rgb.resize(rows.len() * w * 3, 0);
+            rgb.par_chunks_mut(3).zip(bytes.par_chunks(4)).for_each(|(px, c)| {
+                let v = u32::from_le_bytes([c[0], c[1], c[2], c[3]]) as f64 * inv_mx_its;
+                let (r, g, b) = plt.get_col(v, v, factor);
+                px.copy_from_slice(&[r, g, b]);
+            });
+            stream.write_all(&rgb)?;
+            progress.done.store(tiles.len() + i + 1, Ordering::Relaxed);
+        }
+        stream.finish()?;
+        Ok(())
+    };
+    let written = write_png();
+    if written.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    written
+}
@@ -1,5 +1,7 @@
 use rand::{thread_rng, Rng};
 use serde::{Serialize, Deserialize};
+use crate::camera::Camera;
+use crate::integrator::Integrator;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
@@ -10,6 +12,11 @@ pub struct State {
     pub time: f64,
     dt: Option<f64>,
     dt_range: Option<f64>,
+    #[serde(default)]
+    camera: Option<Camera>,
+    // only for flows, older parameter files without it use the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    integrator: Option<Integrator>,
 }
 
 impl Default for State {
@@ -22,6 +29,8 @@ impl Default for State {
             time: 0.0,
             dt: None,
             dt_range: None,
+            camera: None,
+            integrator: None,
         }
     }
 }
@@ -36,8 +45,16 @@ impl State {
             time: 0.0,
             dt: t,
             dt_range: t.map(|t| t * 100.0),
+            camera: None,
+            integrator: t.map(|_| Integrator::default()),
         }
     }
+    // the state starting from `init` instead of 0.5 in every component
+    pub fn with_init(mut self, init: &[f64]) -> Self {
+        self.x = init.to_vec();
+        self.init_x = init.to_vec();
+        self
+    }
     pub fn set_init(&mut self) {
         self.time = 0.0;
         self.x = self.init_x.clone()
@@ -72,6 +89,10 @@ impl State {
     pub fn get_xs_mut(&mut self) -> &mut [f64] {
         &mut self.x
     }
+    // move the state out, for integrators borrowing the system while they update it
+    pub fn take_xs(&mut self) -> Vec<f64> {
+        std::mem::take(&mut self.x)
+    }
     pub fn set_xs(&mut self, new_xs: Vec<f64>) {
         self.x = new_xs;
     }
@@ -84,6 +105,19 @@ impl State {
         self.x[0] = x;
         self.x[1] = y;
     }
+    pub fn integrator(&self) -> Integrator {
+        self.integrator.unwrap_or_default()
+    }
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = Some(integrator);
+    }
+    // None draws the projection of the system itself
+    pub fn camera(&self) -> Option<&Camera> {
+        self.camera.as_ref().filter(|_| self.x.len() >= 3)
+    }
+    pub fn camera_mut(&mut self) -> &mut Option<Camera> {
+        &mut self.camera
+    }
     pub fn get_xyz(&self) -> (f64, f64, f64) {
         assert!(self.x.len() >= 3);
         (self.x[0], self.x[1], self.x[2])